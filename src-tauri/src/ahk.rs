@@ -0,0 +1,371 @@
+//! AHK module - exports scripts as AutoHotkey v2 source
+//! Lets recordings run on machines without AutoKB installed
+//!
+//! Unsupported constructs are kept as `; unsupported:` comments so nothing is dropped silently:
+//! `RunTask` references, unknown mouse buttons and special keys AHK has no name for.
+//! Playback tuning (speed ramp, event padding, click settle, physical keys) is not exported;
+//! the speed multiplier is baked into the Sleep durations.
+
+use crate::analysis::event_type_name;
+use crate::script::{KeyboardKey, MouseButton, Script, ScriptEvent};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// AutoKB special key names with the AHK key name each maps to
+const AHK_KEYS: &[(&str, &str)] = &[
+    ("Alt", "LAlt"),
+    ("AltGr", "RAlt"),
+    ("Apps", "AppsKey"),
+    ("Backspace", "Backspace"),
+    ("CapsLock", "CapsLock"),
+    ("ControlLeft", "LCtrl"),
+    ("ControlRight", "RCtrl"),
+    ("Delete", "Delete"),
+    ("DownArrow", "Down"),
+    ("End", "End"),
+    ("Escape", "Esc"),
+    ("Home", "Home"),
+    ("Insert", "Insert"),
+    ("LeftArrow", "Left"),
+    ("MediaNextTrack", "Media_Next"),
+    ("MediaPlayPause", "Media_Play_Pause"),
+    ("MediaPrevTrack", "Media_Prev"),
+    ("MediaStop", "Media_Stop"),
+    ("MetaLeft", "LWin"),
+    ("MetaRight", "RWin"),
+    ("NumLock", "NumLock"),
+    ("PageDown", "PgDn"),
+    ("PageUp", "PgUp"),
+    ("Pause", "Pause"),
+    ("PrintScreen", "PrintScreen"),
+    ("Return", "Enter"),
+    ("RightArrow", "Right"),
+    ("ScrollLock", "ScrollLock"),
+    ("ShiftLeft", "LShift"),
+    ("ShiftRight", "RShift"),
+    ("Space", "Space"),
+    ("Super", "LWin"),
+    ("Tab", "Tab"),
+    ("UpArrow", "Up"),
+    ("VolumeDown", "Volume_Down"),
+    ("VolumeMute", "Volume_Mute"),
+    ("VolumeUp", "Volume_Up"),
+];
+
+/// AHK key name for use inside `{...}` in a Send string
+fn ahk_key(key: &KeyboardKey) -> Option<String> {
+    match key {
+        KeyboardKey::Char(' ') => Some("Space".to_string()),
+        KeyboardKey::Char('\n') => Some("Enter".to_string()),
+        KeyboardKey::Char('\t') => Some("Tab".to_string()),
+        // Quote and backtick must be escaped inside an AHK string literal
+        KeyboardKey::Char('"') => Some("`\"".to_string()),
+        KeyboardKey::Char('`') => Some("``".to_string()),
+        KeyboardKey::Char(c) => Some(c.to_string()),
+        KeyboardKey::Special(name) => {
+            if let Some((_, ahk)) = AHK_KEYS.iter().find(|(n, _)| n == name) {
+                return Some(ahk.to_string());
+            }
+            // F1-F24 share their names
+            name.strip_prefix('F')
+                .filter(|n| n.parse::<u8>().is_ok_and(|n| (1..=24).contains(&n)))
+                .map(|_| name.clone())
+        }
+    }
+}
+
+fn ahk_button(button: MouseButton) -> Option<&'static str> {
+    match button {
+        MouseButton::Left => Some("Left"),
+        MouseButton::Right => Some("Right"),
+        MouseButton::Middle => Some("Middle"),
+        MouseButton::Back => Some("X1"),
+        MouseButton::Forward => Some("X2"),
+        MouseButton::Unknown | MouseButton::Extra(_) => None,
+    }
+}
+
+/// AHK variable holding a clipboard capture; names are restricted to word characters
+fn ahk_var(var: &str) -> String {
+    let name: String = var
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("v_{}", name)
+}
+
+/// `text` as an AHK string literal
+fn ahk_string(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+    for c in text.chars() {
+        match c {
+            '`' => literal.push_str("``"),
+            '"' => literal.push_str("`\""),
+            '\n' => literal.push_str("`n"),
+            '\r' => literal.push_str("`r"),
+            '\t' => literal.push_str("`t"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Goto label for the event at `index`
+fn ahk_label(index: usize) -> String {
+    format!("e{}", index)
+}
+
+/// Send a key down, up, or both (`action` None)
+fn send_key(key: &KeyboardKey, action: Option<&str>) -> String {
+    match (ahk_key(key), action) {
+        (Some(name), Some(action)) => format!("Send \"{{{} {}}}\"", name, action),
+        (Some(name), None) => format!("Send \"{{{}}}\"", name),
+        (None, _) => format!("; unsupported: key {:?}", key),
+    }
+}
+
+/// AHK lines for one event, without indentation
+fn event_lines(event: &ScriptEvent, speed_multiplier: f64) -> Vec<String> {
+    let sleep = |ms: u64| format!("Sleep {}", (ms as f64 / speed_multiplier).round() as u64);
+    let click = |button: MouseButton, x: f64, y: f64, action: &str| match ahk_button(button) {
+        Some(name) => format!("Click \"{} {} {} {}\"", x.round(), y.round(), name, action),
+        None => format!("; unsupported: mouse button {:?}", button),
+    };
+
+    match event {
+        ScriptEvent::Delay { duration_ms } => vec![sleep(*duration_ms)],
+        ScriptEvent::KeyPress { key, .. } => vec![send_key(key, Some("down"))],
+        ScriptEvent::KeyRelease { key, .. } => vec![send_key(key, Some("up"))],
+        ScriptEvent::MousePress { button, x, y } => vec![click(*button, *x, *y, "Down")],
+        ScriptEvent::MouseRelease { button, x, y } => vec![click(*button, *x, *y, "Up")],
+        ScriptEvent::MouseMove { x, y } => {
+            vec![format!("MouseMove {}, {}, 0", x.round(), y.round())]
+        }
+        ScriptEvent::MouseMoveRel { dx, dy } => vec![format!("MouseMove {}, {}, 0, \"R\"", dx, dy)],
+        ScriptEvent::MouseScroll {
+            delta_x,
+            delta_y,
+            x,
+            y,
+        } => {
+            let position = match (x, y) {
+                (Some(x), Some(y)) => format!("{} {} ", x.round(), y.round()),
+                _ => String::new(),
+            };
+            let mut lines = Vec::new();
+            if *delta_y != 0 {
+                let wheel = if *delta_y > 0 { "WheelUp" } else { "WheelDown" };
+                lines.push(format!("Click \"{}{} {}\"", position, wheel, delta_y.abs()));
+            }
+            if *delta_x != 0 {
+                let wheel = if *delta_x > 0 {
+                    "WheelLeft"
+                } else {
+                    "WheelRight"
+                };
+                lines.push(format!("Click \"{}{} {}\"", position, wheel, delta_x.abs()));
+            }
+            lines
+        }
+        ScriptEvent::IfPixel {
+            x,
+            y,
+            rgb,
+            tolerance,
+            then_jump,
+            else_jump,
+        } => {
+            let (x, y) = (x.round(), y.round());
+            vec![
+                format!(
+                    "if PixelSearch(&_, &_, {x}, {y}, {x}, {y}, 0x{:02X}{:02X}{:02X}, {})",
+                    rgb[0], rgb[1], rgb[2], tolerance
+                ),
+                format!("    Goto \"{}\"", ahk_label(*then_jump)),
+                format!("Goto \"{}\"", ahk_label(*else_jump)),
+            ]
+        }
+        ScriptEvent::WaitForPixelOptional {
+            x,
+            y,
+            rgb,
+            tolerance,
+            max_wait_ms,
+        } => {
+            let (x, y) = (x.round(), y.round());
+            vec![
+                format!("deadline := A_TickCount + {}", max_wait_ms),
+                format!(
+                    "while (A_TickCount < deadline && !PixelSearch(&_, &_, {x}, {y}, {x}, {y}, 0x{:02X}{:02X}{:02X}, {}))",
+                    rgb[0], rgb[1], rgb[2], tolerance
+                ),
+                "    Sleep 50".to_string(),
+            ]
+        }
+        ScriptEvent::Jump { target } => vec![format!("Goto \"{}\"", ahk_label(*target))],
+        ScriptEvent::KeyRepeat {
+            key,
+            count,
+            interval_ms,
+        } => vec![
+            format!("Loop {} {{", count),
+            format!("    {}", send_key(key, None)),
+            format!("    if (A_Index < {})", count),
+            format!("        {}", sleep(*interval_ms)),
+            "}".to_string(),
+        ],
+        ScriptEvent::RunTask { id } => vec![format!("; unsupported: run task {}", id)],
+        ScriptEvent::Disabled { event } => {
+            vec![format!("; disabled: {}", event_type_name(event))]
+        }
+        ScriptEvent::Label { name } => vec![format!("; {}", name)],
+        ScriptEvent::CaptureClipboard { var } => vec![format!("{} := A_Clipboard", ahk_var(var))],
+        ScriptEvent::TypeVar { var } => vec![format!("SendText {}", ahk_var(var))],
+        ScriptEvent::TypeText { text } => vec![format!("SendText {}", ahk_string(text))],
+        ScriptEvent::WindowAnchor { title, .. } => {
+            vec![format!(
+                "; unsupported: coordinates relative to window {:?}",
+                title
+            )]
+        }
+        ScriptEvent::Chord { modifiers, key } => {
+            let names: Option<Vec<String>> = modifiers.iter().map(ahk_key).collect();
+            match (names, ahk_key(key)) {
+                (Some(names), Some(key)) => {
+                    let mut keys = String::new();
+                    for name in &names {
+                        let _ = write!(keys, "{{{} down}}", name);
+                    }
+                    let _ = write!(keys, "{{{}}}", key);
+                    for name in names.iter().rev() {
+                        let _ = write!(keys, "{{{} up}}", name);
+                    }
+                    vec![format!("Send \"{}\"", keys)]
+                }
+                _ => vec![format!("; unsupported: chord {:?}+{:?}", modifiers, key)],
+            }
+        }
+    }
+}
+
+/// Translate a script into a standalone AutoHotkey v2 script
+/// Loops become an AHK Loop; jumps become Goto labels named after the target event index
+pub fn export_ahk(script: &Script) -> String {
+    let speed_multiplier = if script.speed_multiplier > 0.0 {
+        script.speed_multiplier
+    } else {
+        1.0
+    };
+
+    let jump_targets: BTreeSet<usize> = script
+        .events
+        .iter()
+        .flat_map(|event| match event {
+            ScriptEvent::IfPixel {
+                then_jump,
+                else_jump,
+                ..
+            } => vec![*then_jump, *else_jump],
+            ScriptEvent::Jump { target } => vec![*target],
+            _ => Vec::new(),
+        })
+        .collect();
+
+    let mut body = Vec::new();
+    for (index, event) in script.events.iter().enumerate() {
+        if jump_targets.contains(&index) {
+            body.push(format!("{}:", ahk_label(index)));
+        }
+        body.extend(event_lines(event, speed_multiplier));
+    }
+    if jump_targets.contains(&script.events.len()) {
+        body.push(format!("{}:", ahk_label(script.events.len())));
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "; {} - exported from AutoKB", script.name);
+    out.push_str("#Requires AutoHotkey v2.0\n");
+    out.push_str("CoordMode \"Mouse\", \"Screen\"\n");
+    out.push_str("CoordMode \"Pixel\", \"Screen\"\n");
+    out.push_str("SendMode \"Input\"\n\n");
+
+    let loop_config = &script.loop_config;
+    if loop_config.count == 1 {
+        for line in body {
+            let _ = writeln!(out, "{}", line);
+        }
+        return out;
+    }
+
+    // Loop 0 is not infinite in AHK, a bare Loop is
+    match loop_config.count {
+        0 => out.push_str("Loop {\n"),
+        count => {
+            let _ = writeln!(out, "Loop {} {{", count);
+        }
+    }
+    if loop_config.delay_between_ms > 0 {
+        out.push_str("    if (A_Index > 1)\n");
+        let _ = writeln!(
+            out,
+            "        Sleep {}",
+            (loop_config.delay_between_ms as f64 / speed_multiplier).round() as u64
+        );
+    }
+    for line in body {
+        let _ = writeln!(out, "    {}", line);
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_ahk() {
+        let script = Script {
+            name: "Demo".to_string(),
+            events: vec![
+                ScriptEvent::KeyPress {
+                    key: KeyboardKey::Char('"'),
+                    key_code: None,
+                },
+                ScriptEvent::Delay { duration_ms: 100 },
+                ScriptEvent::MousePress {
+                    button: MouseButton::Left,
+                    x: 10.4,
+                    y: 20.6,
+                },
+                ScriptEvent::Chord {
+                    modifiers: vec![KeyboardKey::Special("ControlLeft".to_string())],
+                    key: KeyboardKey::Char('c'),
+                },
+                ScriptEvent::Jump { target: 1 },
+                ScriptEvent::RunTask {
+                    id: "other".to_string(),
+                },
+            ],
+            speed_multiplier: 2.0,
+            ..Script::default()
+        };
+
+        let ahk = export_ahk(&script);
+        let body: Vec<&str> = ahk.lines().skip_while(|l| !l.is_empty()).skip(1).collect();
+        assert_eq!(
+            body,
+            vec![
+                "Send \"{`\" down}\"",
+                "e1:",
+                "Sleep 50",
+                "Click \"10 21 Left Down\"",
+                "Send \"{LCtrl down}{c}{LCtrl up}\"",
+                "Goto \"e1\"",
+                "; unsupported: run task other",
+            ]
+        );
+    }
+}
@@ -0,0 +1,521 @@
+//! Analysis module - read-only computations over event lists
+//! Used by the editor for summaries and visualizations
+
+use crate::script::{KeyboardKey, MouseButton, Script, ScriptEvent};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Total of all Delay durations in milliseconds
+pub fn total_duration_ms(events: &[ScriptEvent]) -> u64 {
+    events
+        .iter()
+        .map(|event| match event {
+            ScriptEvent::Delay { duration_ms } => *duration_ms,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Expected wall-clock playback time, accounting for speed, loops and padding
+/// Infinite loops return `u64::MAX`; jumps and speed ramping are not simulated
+pub fn estimated_playback_ms(script: &Script) -> u64 {
+    if script.loop_config.count == 0 {
+        return u64::MAX;
+    }
+
+    let speed = if script.speed_multiplier > 0.0 {
+        script.speed_multiplier
+    } else {
+        1.0
+    };
+    let scaled = |ms: u64| (ms as f64 / speed) as u64;
+
+    let per_loop: u64 = script
+        .events
+        .iter()
+        .map(|event| {
+            let timed = match event {
+                ScriptEvent::Delay { duration_ms } => scaled(*duration_ms),
+                ScriptEvent::KeyRepeat {
+                    count, interval_ms, ..
+                } => scaled(*interval_ms) * count.saturating_sub(1) as u64,
+                _ => 0,
+            };
+            let padding = if event.is_input() {
+                script.global_event_padding_ms
+            } else {
+                0
+            };
+            timed + padding
+        })
+        .sum();
+
+    // The player waits after every loop, including the last
+    let loops = script.loop_config.count as u64;
+    per_loop
+        .saturating_add(script.loop_config.delay_between_ms)
+        .saturating_mul(loops)
+}
+
+/// Short human-readable summary of the first `max` actions in a script
+/// Consecutive typed characters are merged, e.g. "Click(100,200), Type 'hello', 3 more…"
+pub fn describe_script(script: &Script, max: usize) -> String {
+    let mut actions: Vec<String> = Vec::new();
+    let mut typed = String::new();
+
+    let flush_typed = |typed: &mut String, actions: &mut Vec<String>| {
+        if !typed.is_empty() {
+            actions.push(format!("Type '{}'", typed));
+            typed.clear();
+        }
+    };
+
+    for event in &script.events {
+        let action = match event {
+            ScriptEvent::KeyPress {
+                key: KeyboardKey::Char(c),
+                ..
+            } => {
+                typed.push(*c);
+                continue;
+            }
+            ScriptEvent::KeyPress {
+                key: KeyboardKey::Special(name),
+                ..
+            } => format!("Key({})", name),
+            ScriptEvent::MousePress { button, x, y } => {
+                let name = match button {
+                    MouseButton::Left => "Click",
+                    MouseButton::Right => "RightClick",
+                    MouseButton::Middle => "MiddleClick",
+                    _ => "ButtonClick",
+                };
+                format!("{}({},{})", name, x.round(), y.round())
+            }
+            ScriptEvent::MouseScroll {
+                delta_x, delta_y, ..
+            } => {
+                format!("Scroll({},{})", delta_x, delta_y)
+            }
+            ScriptEvent::IfPixel { x, y, .. } => format!("IfPixel({},{})", x.round(), y.round()),
+            ScriptEvent::WaitForPixelOptional { x, y, .. } => {
+                format!("WaitPixel({},{})", x.round(), y.round())
+            }
+            ScriptEvent::Jump { target } => format!("Jump({})", target),
+            ScriptEvent::KeyRepeat { key, count, .. } => {
+                format!("Repeat({} x{})", key_name(key), count)
+            }
+            ScriptEvent::RunTask { id } => format!("RunTask({})", id),
+            ScriptEvent::Label { name } => format!("[{}]", name),
+            ScriptEvent::CaptureClipboard { var } => format!("Capture({})", var),
+            ScriptEvent::TypeVar { var } => format!("TypeVar({})", var),
+            ScriptEvent::TypeText { text } => format!("Text({:?})", text),
+            ScriptEvent::WindowAnchor { title, .. } => format!("Window({:?})", title),
+            ScriptEvent::Chord { modifiers, key } => {
+                let names: Vec<String> = modifiers
+                    .iter()
+                    .chain(std::iter::once(key))
+                    .map(key_name)
+                    .collect();
+                format!("Chord({})", names.join("+"))
+            }
+            // Delays, releases and moves carry no standalone meaning in a summary
+            _ => continue,
+        };
+        flush_typed(&mut typed, &mut actions);
+        actions.push(action);
+    }
+    flush_typed(&mut typed, &mut actions);
+
+    if actions.is_empty() {
+        return "(empty)".to_string();
+    }
+
+    let remaining = actions.len().saturating_sub(max);
+    actions.truncate(max);
+    if remaining > 0 {
+        actions.push(format!("{} more…", remaining));
+    }
+    actions.join(", ")
+}
+
+fn key_name(key: &KeyboardKey) -> String {
+    match key {
+        KeyboardKey::Char(c) => c.to_string(),
+        KeyboardKey::Special(name) => name.clone(),
+    }
+}
+
+fn button_name(button: MouseButton) -> String {
+    match button {
+        MouseButton::Left => "LMB".to_string(),
+        MouseButton::Right => "RMB".to_string(),
+        MouseButton::Middle => "MMB".to_string(),
+        MouseButton::Back => "Back".to_string(),
+        MouseButton::Forward => "Forward".to_string(),
+        MouseButton::Unknown => "Button?".to_string(),
+        MouseButton::Extra(n) => format!("Button{}", n),
+    }
+}
+
+/// Compact label for one event, as used by `events_to_string`
+fn event_label(event: &ScriptEvent) -> String {
+    match event {
+        ScriptEvent::Delay { duration_ms } => format!("wait {}ms", duration_ms),
+        ScriptEvent::KeyPress { key, .. } => format!("{}↓", key_name(key)),
+        ScriptEvent::KeyRelease { key, .. } => format!("{}↑", key_name(key)),
+        ScriptEvent::MousePress { button, .. } => format!("{}↓", button_name(*button)),
+        ScriptEvent::MouseRelease { button, .. } => format!("{}↑", button_name(*button)),
+        ScriptEvent::MouseMove { x, y } => format!("Move({},{})", x.round(), y.round()),
+        ScriptEvent::MouseMoveRel { dx, dy } => format!("MoveRel({},{})", dx, dy),
+        ScriptEvent::MouseScroll {
+            delta_x, delta_y, ..
+        } => format!("Scroll({},{})", delta_x, delta_y),
+        ScriptEvent::IfPixel {
+            x,
+            y,
+            then_jump,
+            else_jump,
+            ..
+        } => format!(
+            "IfPixel({},{}) ? {} : {}",
+            x.round(),
+            y.round(),
+            then_jump,
+            else_jump
+        ),
+        ScriptEvent::WaitForPixelOptional {
+            x, y, max_wait_ms, ..
+        } => format!("WaitPixel({},{}) ≤{}ms", x.round(), y.round(), max_wait_ms),
+        ScriptEvent::Jump { target } => format!("Jump({})", target),
+        ScriptEvent::KeyRepeat {
+            key,
+            count,
+            interval_ms,
+        } => format!("{} x{} every {}ms", key_name(key), count, interval_ms),
+        ScriptEvent::RunTask { id } => format!("RunTask({})", id),
+        ScriptEvent::Disabled { event } => format!("off: {}", event_label(event)),
+        ScriptEvent::Label { name } => format!("#{}", name),
+        ScriptEvent::CaptureClipboard { var } => format!("Capture({})", var),
+        ScriptEvent::TypeVar { var } => format!("TypeVar({})", var),
+        ScriptEvent::TypeText { text } => format!("Text \"{}\"", text),
+        ScriptEvent::WindowAnchor { title, x, y } => {
+            format!("Window \"{}\" at ({}, {})", title, x.round(), y.round())
+        }
+        ScriptEvent::Chord { modifiers, key } => {
+            let names: Vec<String> = modifiers
+                .iter()
+                .chain(std::iter::once(key))
+                .map(key_name)
+                .collect();
+            names.join("+")
+        }
+    }
+}
+
+/// One-line text rendering of an event list, e.g. `[Move(100,200)][LMB↓][LMB↑ wait 50ms]`
+/// Runs of typed characters become `[Type "hi"]`; delays attach to the event before them
+pub fn events_to_string(events: &[ScriptEvent]) -> String {
+    let mut tokens: Vec<String> = Vec::new();
+    // Characters typed so far in the current run, and the time waited within it
+    let mut typing: Option<(String, u64)> = None;
+
+    let flush_typing = |typing: &mut Option<(String, u64)>, tokens: &mut Vec<String>| {
+        if let Some((text, waited_ms)) = typing.take() {
+            let mut token = format!("Type \"{}\"", text);
+            if waited_ms > 0 {
+                token.push_str(&format!(" wait {}ms", waited_ms));
+            }
+            tokens.push(token);
+        }
+    };
+
+    for event in events {
+        match (event, typing.as_mut()) {
+            (
+                ScriptEvent::KeyPress {
+                    key: KeyboardKey::Char(c),
+                    ..
+                },
+                _,
+            ) => {
+                typing.get_or_insert_with(Default::default).0.push(*c);
+                continue;
+            }
+            (
+                ScriptEvent::KeyRelease {
+                    key: KeyboardKey::Char(_),
+                    ..
+                },
+                Some(_),
+            ) => continue,
+            (ScriptEvent::Delay { duration_ms }, Some((_, waited_ms))) => {
+                *waited_ms += duration_ms;
+                continue;
+            }
+            _ => {}
+        }
+
+        flush_typing(&mut typing, &mut tokens);
+        match (event, tokens.last_mut()) {
+            (ScriptEvent::Delay { duration_ms }, Some(last)) => {
+                last.push_str(&format!(" wait {}ms", duration_ms));
+            }
+            _ => tokens.push(event_label(event)),
+        }
+    }
+    flush_typing(&mut typing, &mut tokens);
+
+    tokens.iter().map(|token| format!("[{}]", token)).collect()
+}
+
+/// Serialized `event_type` tag of an event
+pub fn event_type_name(event: &ScriptEvent) -> &'static str {
+    match event {
+        ScriptEvent::Delay { .. } => "Delay",
+        ScriptEvent::KeyPress { .. } => "KeyPress",
+        ScriptEvent::KeyRelease { .. } => "KeyRelease",
+        ScriptEvent::MousePress { .. } => "MousePress",
+        ScriptEvent::MouseRelease { .. } => "MouseRelease",
+        ScriptEvent::MouseMove { .. } => "MouseMove",
+        ScriptEvent::MouseMoveRel { .. } => "MouseMoveRel",
+        ScriptEvent::MouseScroll { .. } => "MouseScroll",
+        ScriptEvent::IfPixel { .. } => "IfPixel",
+        ScriptEvent::WaitForPixelOptional { .. } => "WaitForPixelOptional",
+        ScriptEvent::Jump { .. } => "Jump",
+        ScriptEvent::KeyRepeat { .. } => "KeyRepeat",
+        ScriptEvent::RunTask { .. } => "RunTask",
+        ScriptEvent::Disabled { .. } => "Disabled",
+        ScriptEvent::Label { .. } => "Label",
+        ScriptEvent::CaptureClipboard { .. } => "CaptureClipboard",
+        ScriptEvent::TypeVar { .. } => "TypeVar",
+        ScriptEvent::TypeText { .. } => "TypeText",
+        ScriptEvent::WindowAnchor { .. } => "WindowAnchor",
+        ScriptEvent::Chord { .. } => "Chord",
+    }
+}
+
+/// Composition and size of a script, for deciding which optimizations are worth running
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptMetrics {
+    /// Number of events per `event_type`
+    pub event_counts: BTreeMap<String, usize>,
+    /// Sum of all delays at 1x speed
+    pub total_duration_ms: u64,
+    /// Size of the script as saved to disk
+    pub byte_size: usize,
+    /// See `estimated_playback_ms`
+    pub estimated_playback_ms: u64,
+}
+
+/// Summarize a script's composition, size and timing in one pass
+pub fn script_metrics(script: &Script) -> Result<ScriptMetrics, String> {
+    let mut event_counts = BTreeMap::new();
+    for event in &script.events {
+        *event_counts
+            .entry(event_type_name(event).to_string())
+            .or_insert(0) += 1;
+    }
+    let byte_size = serde_json::to_string_pretty(script)
+        .map_err(|e| format!("Serialization error: {}", e))?
+        .len();
+
+    Ok(ScriptMetrics {
+        event_counts,
+        total_duration_ms: total_duration_ms(&script.events),
+        byte_size,
+        estimated_playback_ms: estimated_playback_ms(script),
+    })
+}
+
+/// A delay after `scale_delays` with `factor`
+pub fn scaled_delay(duration_ms: u64, factor: f64) -> u64 {
+    (duration_ms as f64 * factor) as u64
+}
+
+/// Timing of an event list before and after `scale_delays`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DurationPreview {
+    pub before_ms: u64,
+    pub after_ms: u64,
+    /// Shortest and longest delay after scaling (None without delays)
+    pub min_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>,
+}
+
+/// What `scale_delays` would do to the timing, without building the scaled list
+pub fn preview_scale_delays(events: &[ScriptEvent], factor: f64) -> DurationPreview {
+    let scaled: Vec<u64> = events
+        .iter()
+        .filter_map(|event| match event {
+            ScriptEvent::Delay { duration_ms } => Some(scaled_delay(*duration_ms, factor)),
+            _ => None,
+        })
+        .collect();
+
+    DurationPreview {
+        before_ms: total_duration_ms(events),
+        after_ms: scaled.iter().sum(),
+        min_delay_ms: scaled.iter().copied().min(),
+        max_delay_ms: scaled.iter().copied().max(),
+    }
+}
+
+/// Bucket non-delay events by cumulative time into `buckets` slots
+/// Returns the event count per slot, for drawing an activity histogram
+pub fn timeline_summary(events: &[ScriptEvent], buckets: usize) -> Vec<u32> {
+    let mut counts = vec![0u32; buckets];
+    if buckets == 0 {
+        return counts;
+    }
+
+    let total = total_duration_ms(events);
+    let mut elapsed = 0u64;
+    for event in events {
+        match event {
+            ScriptEvent::Delay { duration_ms } => elapsed += duration_ms,
+            _ => {
+                let slot = if total == 0 {
+                    0
+                } else {
+                    ((elapsed as u128 * buckets as u128) / total as u128) as usize
+                };
+                counts[slot.min(buckets - 1)] += 1;
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_press() -> ScriptEvent {
+        ScriptEvent::KeyPress {
+            key: KeyboardKey::Char('a'),
+            key_code: None,
+        }
+    }
+
+    #[test]
+    fn test_preview_scale_delays() {
+        let events = vec![
+            ScriptEvent::Delay { duration_ms: 100 },
+            key_press(),
+            ScriptEvent::Delay { duration_ms: 35 },
+        ];
+        assert_eq!(
+            preview_scale_delays(&events, 0.5),
+            DurationPreview {
+                before_ms: 135,
+                after_ms: 67,
+                min_delay_ms: Some(17),
+                max_delay_ms: Some(50),
+            }
+        );
+        assert_eq!(preview_scale_delays(&[key_press()], 2.0).max_delay_ms, None);
+    }
+
+    #[test]
+    fn test_timeline_summary_buckets_by_time() {
+        let events = vec![
+            key_press(),
+            key_press(),
+            ScriptEvent::Delay { duration_ms: 100 },
+            key_press(),
+        ];
+        assert_eq!(timeline_summary(&events, 2), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_estimated_playback_ms() {
+        let mut script = Script {
+            events: vec![ScriptEvent::Delay { duration_ms: 1000 }, key_press()],
+            speed_multiplier: 2.0,
+            ..Default::default()
+        };
+        script.loop_config.count = 3;
+        script.loop_config.delay_between_ms = 100;
+        assert_eq!(estimated_playback_ms(&script), 3 * (500 + 100));
+
+        script.loop_config.count = 0;
+        assert_eq!(estimated_playback_ms(&script), u64::MAX);
+    }
+
+    #[test]
+    fn test_describe_script_merges_typing() {
+        let script = Script {
+            events: vec![
+                ScriptEvent::MousePress {
+                    button: MouseButton::Left,
+                    x: 100.0,
+                    y: 200.0,
+                },
+                ScriptEvent::MouseRelease {
+                    button: MouseButton::Left,
+                    x: 100.0,
+                    y: 200.0,
+                },
+                ScriptEvent::Delay { duration_ms: 50 },
+                key_press(),
+                key_press(),
+                ScriptEvent::KeyPress {
+                    key: KeyboardKey::Special("Return".to_string()),
+                    key_code: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            describe_script(&script, 5),
+            "Click(100,200), Type 'aa', Key(Return)"
+        );
+        assert_eq!(describe_script(&script, 1), "Click(100,200), 2 more…");
+        assert_eq!(describe_script(&Script::default(), 3), "(empty)");
+    }
+
+    #[test]
+    fn test_events_to_string() {
+        let events = vec![
+            ScriptEvent::MouseMove { x: 100.0, y: 200.0 },
+            ScriptEvent::MousePress {
+                button: MouseButton::Left,
+                x: 100.0,
+                y: 200.0,
+            },
+            ScriptEvent::MouseRelease {
+                button: MouseButton::Left,
+                x: 100.0,
+                y: 200.0,
+            },
+            ScriptEvent::Delay { duration_ms: 50 },
+            key_press(),
+            ScriptEvent::Delay { duration_ms: 10 },
+            ScriptEvent::KeyRelease {
+                key: KeyboardKey::Char('a'),
+                key_code: None,
+            },
+            ScriptEvent::KeyPress {
+                key: KeyboardKey::Char('b'),
+                key_code: None,
+            },
+            ScriptEvent::Label {
+                name: "end".to_string(),
+            },
+        ];
+        assert_eq!(
+            events_to_string(&events),
+            "[Move(100,200)][LMB↓][LMB↑ wait 50ms][Type \"ab\" wait 10ms][#end]"
+        );
+        assert_eq!(events_to_string(&[]), "");
+    }
+
+    #[test]
+    fn test_timeline_summary_without_delays() {
+        let events = vec![key_press(), key_press()];
+        assert_eq!(timeline_summary(&events, 3), vec![2, 0, 0]);
+        assert!(timeline_summary(&events, 0).is_empty());
+    }
+}
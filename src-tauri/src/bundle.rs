@@ -0,0 +1,384 @@
+//! Bundle module - exports and imports the whole library as a single zip archive
+//! Contains all .autokb scripts plus the persisted tasks and settings
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+
+/// Data files (relative to the data dir) included alongside the scripts
+const DATA_FILES: [&str; 2] = [TASKS_FILE, "config.json"];
+
+/// Task list, whose script paths are stored relative to the scripts dir inside a bundle
+const TASKS_FILE: &str = "tasks.json";
+
+/// Archive folder holding the scripts
+const SCRIPTS_PREFIX: &str = "scripts/";
+
+/// Result of importing a bundle
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct ImportSummary {
+    /// Archive entries written to the data dir
+    pub imported: Vec<String>,
+    /// Archive entries skipped (existing files or unknown entries)
+    pub skipped: Vec<String>,
+}
+
+/// Write all scripts, tasks and settings under `data_dir` into a zip at `path`
+pub fn export_bundle(data_dir: &Path, path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("File write error: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+
+    let scripts_dir = data_dir.join("scripts");
+    if scripts_dir.exists() {
+        let entries = fs::read_dir(&scripts_dir).map_err(|e| e.to_string())?;
+        for entry in entries.flatten() {
+            let script_path = entry.path();
+            if script_path.extension().and_then(|s| s.to_str()) != Some("autokb") {
+                continue;
+            }
+            if let Some(name) = script_path.file_name().and_then(|s| s.to_str()) {
+                let content =
+                    fs::read(&script_path).map_err(|e| format!("File read error: {}", e))?;
+                add_entry(&mut zip, &format!("{}{}", SCRIPTS_PREFIX, name), &content)?;
+            }
+        }
+    }
+
+    for name in DATA_FILES {
+        let data_path = data_dir.join(name);
+        if !data_path.exists() {
+            continue;
+        }
+        let mut content = fs::read(&data_path).map_err(|e| format!("File read error: {}", e))?;
+        if name == TASKS_FILE {
+            // The data dir differs between machines, so only keep the part under it
+            content = rewrite_script_paths(&content, |path| {
+                Path::new(path)
+                    .strip_prefix(&scripts_dir)
+                    .ok()
+                    .and_then(|relative| relative.to_str())
+                    .map(str::to_string)
+            })?;
+        }
+        add_entry(&mut zip, name, &content)?;
+    }
+
+    zip.finish().map_err(|e| format!("Zip error: {}", e))?;
+    Ok(())
+}
+
+fn add_entry(zip: &mut zip::ZipWriter<File>, name: &str, content: &[u8]) -> Result<(), String> {
+    zip.start_file(name, FileOptions::default())
+        .map_err(|e| format!("Zip error: {}", e))?;
+    zip.write_all(content)
+        .map_err(|e| format!("Zip error: {}", e))
+}
+
+/// Pass each task's `script_path` in a tasks file through `rewrite`, keeping it if that gives None
+fn rewrite_script_paths(
+    content: &[u8],
+    rewrite: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<u8>, String> {
+    let mut tasks: serde_json::Value =
+        serde_json::from_slice(content).map_err(|e| format!("Parse error: {}", e))?;
+    for task in tasks.as_array_mut().into_iter().flatten() {
+        if let Some(serde_json::Value::String(path)) = task.get_mut("script_path") {
+            if let Some(rewritten) = rewrite(path) {
+                *path = rewritten;
+            }
+        }
+    }
+    serde_json::to_string_pretty(&tasks)
+        .map(String::into_bytes)
+        .map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Whether an archive entry is one we know how to import
+/// Rejects anything that could escape the data dir
+fn is_importable(name: &str) -> bool {
+    if DATA_FILES.contains(&name) {
+        return true;
+    }
+    match name.strip_prefix(SCRIPTS_PREFIX) {
+        Some(file) => {
+            file.ends_with(".autokb")
+                && !file.contains('/')
+                && !file.contains('\\')
+                && !file.starts_with('.')
+        }
+        None => false,
+    }
+}
+
+/// Extract a bundle at `path` into `data_dir`
+/// Existing files are kept unless `overwrite` is set
+pub fn import_bundle(
+    data_dir: &Path,
+    path: &Path,
+    overwrite: bool,
+) -> Result<ImportSummary, String> {
+    let file = File::open(path).map_err(|e| format!("File read error: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Zip error: {}", e))?;
+    let mut summary = ImportSummary::default();
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Zip error: {}", e))?;
+        let name = entry.name().to_string();
+
+        let target = data_dir.join(&name);
+        if !is_importable(&name) || (target.exists() && !overwrite) {
+            summary.skipped.push(name);
+            continue;
+        }
+
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Zip error: {}", e))?;
+        if name == TASKS_FILE {
+            let scripts_dir = data_dir.join("scripts");
+            content = rewrite_script_paths(&content, |path| {
+                (!path.is_empty() && Path::new(path).is_relative())
+                    .then(|| scripts_dir.join(path).to_string_lossy().into_owned())
+            })?;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        fs::write(&target, content).map_err(|e| format!("File write error: {}", e))?;
+        summary.imported.push(name);
+    }
+
+    Ok(summary)
+}
+
+/// What to do when an imported script has the same name as an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub enum ConflictPolicy {
+    /// Keep the existing script
+    Skip,
+    /// Replace the existing script
+    Overwrite,
+    /// Import under a free name like `name-2.autokb`
+    Rename,
+}
+
+/// First `stem-N.autokb` in `dir` that doesn't exist yet
+fn free_script_path(dir: &Path, stem: &str) -> PathBuf {
+    (2..)
+        .map(|n| dir.join(format!("{}-{}.autokb", stem, n)))
+        .find(|path| !path.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Copy every .autokb file in `source` into `scripts_dir`, resolving name clashes per `policy`
+/// Other files and subdirectories are ignored
+pub fn import_scripts_from_dir(
+    source: &Path,
+    scripts_dir: &Path,
+    policy: ConflictPolicy,
+) -> Result<ImportSummary, String> {
+    let entries = fs::read_dir(source).map_err(|e| format!("Directory read error: {}", e))?;
+    fs::create_dir_all(scripts_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    // Copying a file onto itself would truncate it
+    if fs::canonicalize(source).ok() == fs::canonicalize(scripts_dir).ok() {
+        return Err("Source is the scripts directory".to_string());
+    }
+    let mut summary = ImportSummary::default();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("autokb") {
+            continue;
+        }
+        let (Some(name), Some(stem)) = (
+            path.file_name().and_then(|s| s.to_str()),
+            path.file_stem().and_then(|s| s.to_str()),
+        ) else {
+            continue;
+        };
+
+        let mut target = scripts_dir.join(name);
+        if target.exists() {
+            match policy {
+                ConflictPolicy::Skip => {
+                    summary.skipped.push(name.to_string());
+                    continue;
+                }
+                ConflictPolicy::Overwrite => {}
+                ConflictPolicy::Rename => target = free_script_path(scripts_dir, stem),
+            }
+        }
+
+        fs::copy(&path, &target).map_err(|e| format!("File write error: {}", e))?;
+        if let Some(imported) = target.file_name().and_then(|s| s.to_str()) {
+            summary.imported.push(imported.to_string());
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh empty directory under the system temp dir
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("autokb-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn tasks_json(script_path: &Path) -> String {
+        serde_json::json!([{ "id": "task_1", "script_path": script_path.to_string_lossy() }])
+            .to_string()
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let source = temp_dir("bundle-source");
+        fs::create_dir_all(source.join("scripts")).unwrap();
+        let script = source.join("scripts").join("login.autokb");
+        fs::write(&script, "{}").unwrap();
+        fs::write(source.join("scripts").join("notes.txt"), "ignored").unwrap();
+        fs::write(source.join("tasks.json"), tasks_json(&script)).unwrap();
+        fs::write(source.join("config.json"), "{}").unwrap();
+
+        let archive = source.join("library.zip");
+        export_bundle(&source, &archive).unwrap();
+
+        let target = temp_dir("bundle-target");
+        let summary = import_bundle(&target, &archive, false).unwrap();
+        assert_eq!(summary.imported.len(), 3);
+        assert!(summary.skipped.is_empty());
+        assert_eq!(
+            fs::read_to_string(target.join("scripts").join("login.autokb")).unwrap(),
+            "{}"
+        );
+        assert!(!target.join("scripts").join("notes.txt").exists());
+
+        // The task follows its script into the new data dir
+        let tasks: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(target.join("tasks.json")).unwrap()).unwrap();
+        let imported_path = target.join("scripts").join("login.autokb");
+        let imported_path = imported_path.to_string_lossy();
+        assert_eq!(tasks[0]["script_path"].as_str(), Some(&*imported_path));
+
+        // Without overwrite, a second import keeps what is there
+        let again = import_bundle(&target, &archive, false).unwrap();
+        assert!(again.imported.is_empty());
+        assert_eq!(again.skipped.len(), 3);
+
+        let _ = fs::remove_dir_all(source);
+        let _ = fs::remove_dir_all(target);
+    }
+
+    #[test]
+    fn test_importable_rejects_escaping_names() {
+        assert!(is_importable("tasks.json"));
+        assert!(is_importable("config.json"));
+        assert!(is_importable("scripts/login.autokb"));
+
+        assert!(!is_importable("scripts/../tasks.autokb"));
+        assert!(!is_importable("../scripts/login.autokb"));
+        assert!(!is_importable("scripts/nested/login.autokb"));
+        assert!(!is_importable("scripts\\..\\login.autokb"));
+        assert!(!is_importable("scripts/.hidden.autokb"));
+        assert!(!is_importable("scripts/login.json"));
+        assert!(!is_importable("other.json"));
+    }
+
+    /// A source folder with `login.autokb` and a scripts dir that already has one
+    fn conflicting_dirs(name: &str) -> (PathBuf, PathBuf) {
+        let root = temp_dir(name);
+        let source = root.join("source");
+        let scripts = root.join("scripts");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&scripts).unwrap();
+        fs::write(source.join("login.autokb"), "new").unwrap();
+        fs::write(source.join("export.autokb"), "export").unwrap();
+        fs::write(source.join("readme.txt"), "ignored").unwrap();
+        fs::write(scripts.join("login.autokb"), "old").unwrap();
+        (source, scripts)
+    }
+
+    fn sorted(mut names: Vec<String>) -> Vec<String> {
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_import_dir_skip_keeps_existing() {
+        let (source, scripts) = conflicting_dirs("import-skip");
+        let summary = import_scripts_from_dir(&source, &scripts, ConflictPolicy::Skip).unwrap();
+
+        assert_eq!(summary.imported, vec!["export.autokb"]);
+        assert_eq!(summary.skipped, vec!["login.autokb"]);
+        assert_eq!(
+            fs::read_to_string(scripts.join("login.autokb")).unwrap(),
+            "old"
+        );
+        assert!(!scripts.join("readme.txt").exists());
+    }
+
+    #[test]
+    fn test_import_dir_overwrite_replaces_existing() {
+        let (source, scripts) = conflicting_dirs("import-overwrite");
+        let summary =
+            import_scripts_from_dir(&source, &scripts, ConflictPolicy::Overwrite).unwrap();
+
+        assert_eq!(
+            sorted(summary.imported),
+            vec!["export.autokb", "login.autokb"]
+        );
+        assert!(summary.skipped.is_empty());
+        assert_eq!(
+            fs::read_to_string(scripts.join("login.autokb")).unwrap(),
+            "new"
+        );
+    }
+
+    #[test]
+    fn test_import_dir_rename_keeps_both() {
+        let (source, scripts) = conflicting_dirs("import-rename");
+        let summary = import_scripts_from_dir(&source, &scripts, ConflictPolicy::Rename).unwrap();
+
+        assert_eq!(
+            sorted(summary.imported),
+            vec!["export.autokb", "login-2.autokb"]
+        );
+        assert_eq!(
+            fs::read_to_string(scripts.join("login.autokb")).unwrap(),
+            "old"
+        );
+        assert_eq!(
+            fs::read_to_string(scripts.join("login-2.autokb")).unwrap(),
+            "new"
+        );
+    }
+
+    #[test]
+    fn test_import_dir_refuses_scripts_dir_itself() {
+        let (_, scripts) = conflicting_dirs("import-self");
+        assert!(import_scripts_from_dir(&scripts, &scripts, ConflictPolicy::Overwrite).is_err());
+        assert_eq!(
+            fs::read_to_string(scripts.join("login.autokb")).unwrap(),
+            "old"
+        );
+    }
+
+    #[test]
+    fn test_free_script_path_skips_taken_names() {
+        let dir = temp_dir("free-path");
+        assert_eq!(free_script_path(&dir, "login"), dir.join("login-2.autokb"));
+
+        fs::write(dir.join("login-2.autokb"), "").unwrap();
+        fs::write(dir.join("login-3.autokb"), "").unwrap();
+        assert_eq!(free_script_path(&dir, "login"), dir.join("login-4.autokb"));
+    }
+}
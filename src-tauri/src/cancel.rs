@@ -0,0 +1,71 @@
+//! Cancel module - shared cancellation flag for long-running operations
+//! Blocking work polls a token; `cancel_all` trips every registered token
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Tokens reachable from `cancel_all`
+static REGISTRY: Lazy<Mutex<Vec<CancellationToken>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A cheaply cloneable cancellation flag; clones share the same flag
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that `cancel_all` will also cancel
+    pub fn registered() -> Self {
+        let token = Self::new();
+        let mut registry = REGISTRY.lock();
+        // Drop tokens nobody but the registry holds anymore
+        registry.retain(|t| Arc::strong_count(&t.cancelled) > 1);
+        registry.push(token.clone());
+        token
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Clear the flag so the token can be reused for the next operation
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Cancel every registered token
+pub fn cancel_all() {
+    for token in REGISTRY.lock().iter() {
+        token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_all_reaches_registered_tokens() {
+        let registered = CancellationToken::registered();
+        let clone = registered.clone();
+        let local = CancellationToken::new();
+
+        cancel_all();
+        assert!(clone.is_cancelled());
+        assert!(!local.is_cancelled());
+
+        registered.reset();
+        assert!(!clone.is_cancelled());
+    }
+}
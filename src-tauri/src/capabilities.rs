@@ -0,0 +1,40 @@
+//! Capabilities module - reports which input features work on this platform
+//! Lets the frontend hide options that would fail at runtime
+
+use std::env;
+
+/// Platform feature matrix
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Capabilities {
+    /// Operating system name (windows, macos, linux)
+    pub os: String,
+    /// Global input capture via the low-level listener
+    pub can_listen: bool,
+    /// Suppressing input from other apps (rdev grab; not enabled in this build)
+    pub can_grab: bool,
+    /// Reading screen pixels for IfPixel events
+    pub has_pixel_capture: bool,
+    /// Relative mouse movement on playback
+    pub supports_relative_move: bool,
+    /// Horizontal scrolling on playback
+    pub supports_horizontal_scroll: bool,
+}
+
+/// Whether the session is running under Wayland, where global hooks don't work
+fn is_wayland() -> bool {
+    cfg!(target_os = "linux")
+        && (env::var_os("WAYLAND_DISPLAY").is_some()
+            || env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland")))
+}
+
+/// Detect the capabilities of the current platform
+pub fn detect() -> Capabilities {
+    Capabilities {
+        os: env::consts::OS.to_string(),
+        can_listen: !is_wayland(),
+        can_grab: false,
+        has_pixel_capture: screenshots::Screen::all().is_ok_and(|screens| !screens.is_empty()),
+        supports_relative_move: true,
+        supports_horizontal_scroll: true,
+    }
+}
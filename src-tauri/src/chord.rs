@@ -0,0 +1,212 @@
+//! Chord module - modifier handling for edited scripts
+//! Collapses combos into Chord events and repairs held-modifier regions
+
+use crate::script::{KeyboardKey, ScriptEvent};
+
+/// Try to read a chord starting at `start`
+/// Returns the chord, the delay recorded inside it, and the index just past it
+fn match_chord(events: &[ScriptEvent], start: usize) -> Option<(ScriptEvent, u64, usize)> {
+    let mut modifiers: Vec<KeyboardKey> = Vec::new();
+    let mut inner_delay = 0;
+    let mut i = start;
+
+    // Modifier presses, then the key press
+    let key = loop {
+        match events.get(i)? {
+            ScriptEvent::KeyPress { key, .. } if key.is_modifier() => {
+                if modifiers.contains(key) {
+                    return None;
+                }
+                modifiers.push(key.clone());
+            }
+            ScriptEvent::KeyPress { key, .. } if !modifiers.is_empty() => break key.clone(),
+            ScriptEvent::Delay { duration_ms } if !modifiers.is_empty() => {
+                inner_delay += duration_ms
+            }
+            _ => return None,
+        }
+        i += 1;
+    };
+    i += 1;
+
+    // The key release, then every modifier release in any order
+    let mut key_released = false;
+    let mut held = modifiers.clone();
+    while !key_released || !held.is_empty() {
+        match events.get(i)? {
+            ScriptEvent::Delay { duration_ms } => inner_delay += duration_ms,
+            ScriptEvent::KeyRelease { key: released, .. } if !key_released && *released == key => {
+                key_released = true
+            }
+            ScriptEvent::KeyRelease { key: released, .. } if key_released => {
+                let position = held.iter().position(|m| m == released)?;
+                held.remove(position);
+            }
+            _ => return None,
+        }
+        i += 1;
+    }
+
+    Some((ScriptEvent::Chord { modifiers, key }, inner_delay, i))
+}
+
+/// Replace modifier+key sequences with Chord events
+/// Delays inside a chord are kept as one Delay after it, so total timing is unchanged
+pub fn collapse_chords(events: Vec<ScriptEvent>) -> Vec<ScriptEvent> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut i = 0;
+    while i < events.len() {
+        match match_chord(&events, i) {
+            Some((chord, inner_delay, next)) => {
+                out.push(chord);
+                if inner_delay > 0 {
+                    out.push(ScriptEvent::Delay {
+                        duration_ms: inner_delay,
+                    });
+                }
+                i = next;
+            }
+            None => {
+                out.push(events[i].clone());
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Repair held-modifier regions broken by editing
+/// Drops releases whose press was deleted and repeated presses of an already held
+/// modifier, then releases anything still held at the end, in reverse press order
+pub fn ensure_modifier_brackets(events: Vec<ScriptEvent>) -> Vec<ScriptEvent> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut held: Vec<KeyboardKey> = Vec::new();
+
+    for event in events {
+        match &event {
+            ScriptEvent::KeyPress { key, .. } if key.is_modifier() => {
+                if held.contains(key) {
+                    continue;
+                }
+                held.push(key.clone());
+            }
+            ScriptEvent::KeyRelease { key, .. } if key.is_modifier() => {
+                match held.iter().position(|k| k == key) {
+                    Some(position) => {
+                        held.remove(position);
+                    }
+                    None => continue,
+                }
+            }
+            _ => {}
+        }
+        out.push(event);
+    }
+
+    for key in held.into_iter().rev() {
+        out.push(ScriptEvent::KeyRelease {
+            key,
+            key_code: None,
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn special(name: &str) -> KeyboardKey {
+        KeyboardKey::Special(name.to_string())
+    }
+
+    fn press(key: KeyboardKey) -> ScriptEvent {
+        ScriptEvent::KeyPress {
+            key,
+            key_code: None,
+        }
+    }
+
+    fn release(key: KeyboardKey) -> ScriptEvent {
+        ScriptEvent::KeyRelease {
+            key,
+            key_code: None,
+        }
+    }
+
+    #[test]
+    fn test_collapse_chords() {
+        let ctrl = special("ControlLeft");
+        let shift = special("ShiftLeft");
+        let c = KeyboardKey::Char('c');
+        let events = vec![
+            press(ctrl.clone()),
+            press(shift.clone()),
+            ScriptEvent::Delay { duration_ms: 30 },
+            press(c.clone()),
+            release(c.clone()),
+            release(ctrl.clone()),
+            ScriptEvent::Delay { duration_ms: 20 },
+            release(shift.clone()),
+            press(c.clone()),
+            release(c.clone()),
+        ];
+
+        assert_eq!(
+            collapse_chords(events),
+            vec![
+                ScriptEvent::Chord {
+                    modifiers: vec![ctrl, shift],
+                    key: c.clone(),
+                },
+                ScriptEvent::Delay { duration_ms: 50 },
+                press(c.clone()),
+                release(c),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ensure_modifier_brackets() {
+        let shift = special("ShiftLeft");
+        let ctrl = special("ControlLeft");
+        let click = ScriptEvent::MousePress {
+            button: crate::script::MouseButton::Left,
+            x: 0.0,
+            y: 0.0,
+        };
+        let events = vec![
+            release(ctrl.clone()),
+            press(shift.clone()),
+            press(ctrl.clone()),
+            click.clone(),
+            press(shift.clone()),
+            click.clone(),
+        ];
+
+        assert_eq!(
+            ensure_modifier_brackets(events),
+            vec![
+                press(shift.clone()),
+                press(ctrl.clone()),
+                click.clone(),
+                click,
+                release(ctrl),
+                release(shift),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_chords_leaves_incomplete_sequences() {
+        let ctrl = special("ControlLeft");
+        // Modifier released before the key: not a clean chord
+        let events = vec![
+            press(ctrl.clone()),
+            press(KeyboardKey::Char('c')),
+            release(ctrl),
+            release(KeyboardKey::Char('c')),
+        ];
+        assert_eq!(collapse_chords(events.clone()), events);
+    }
+}
@@ -0,0 +1,313 @@
+//! Diff module - compares two event lists
+//! Aligns events with a longest-common-subsequence over event signatures
+
+use crate::analysis::event_type_name;
+use crate::script::ScriptEvent;
+use serde::Serialize;
+
+/// A single difference between two scripts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind")]
+pub enum ScriptDiff {
+    /// Event present only in the new script
+    Inserted { index_b: usize, event: ScriptEvent },
+    /// Event present only in the old script
+    Deleted { index_a: usize, event: ScriptEvent },
+    /// Aligned Delay events with different durations
+    DelayChanged {
+        index_a: usize,
+        index_b: usize,
+        from_ms: u64,
+        to_ms: u64,
+    },
+}
+
+/// Whether two events align: any two delays align, other events must be equal
+fn same_signature(a: &ScriptEvent, b: &ScriptEvent) -> bool {
+    match (a, b) {
+        (ScriptEvent::Delay { .. }, ScriptEvent::Delay { .. }) => true,
+        _ => a == b,
+    }
+}
+
+/// Largest LCS table `diff_events` will build, in cells (4 bytes each)
+/// Bounds memory for long, very different recordings; common ends don't count
+const MAX_DIFF_CELLS: usize = 16_000_000;
+
+/// Record a DelayChanged if two aligned events are delays of different lengths
+fn push_aligned(diffs: &mut Vec<ScriptDiff>, a: &ScriptEvent, b: &ScriptEvent, i: usize, j: usize) {
+    if let (ScriptEvent::Delay { duration_ms: from }, ScriptEvent::Delay { duration_ms: to }) =
+        (a, b)
+    {
+        if from != to {
+            diffs.push(ScriptDiff::DelayChanged {
+                index_a: i,
+                index_b: j,
+                from_ms: *from,
+                to_ms: *to,
+            });
+        }
+    }
+}
+
+/// Compute the differences turning `a` into `b`
+/// Fails if the scripts differ over too long a stretch to align in bounded memory
+pub fn diff_events(a: &[ScriptEvent], b: &[ScriptEvent]) -> Result<Vec<ScriptDiff>, String> {
+    // Matching ends align trivially, so only the differing middle needs the table
+    let prefix = a
+        .iter()
+        .zip(b)
+        .take_while(|(x, y)| same_signature(x, y))
+        .count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| same_signature(x, y))
+        .count();
+    let (n, m) = (a.len() - suffix, b.len() - suffix);
+
+    let cells = (n - prefix + 1).saturating_mul(m - prefix + 1);
+    if cells > MAX_DIFF_CELLS {
+        return Err(format!(
+            "Diff error: scripts differ over {} x {} events, too many to compare",
+            n - prefix,
+            m - prefix
+        ));
+    }
+
+    let mut diffs = Vec::new();
+    for i in 0..prefix {
+        push_aligned(&mut diffs, &a[i], &b[i], i, i);
+    }
+
+    // lcs[(i - prefix) * width + (j - prefix)] = LCS length of a[i..n] and b[j..m]
+    let width = m - prefix + 1;
+    let at = |i: usize, j: usize| (i - prefix) * width + (j - prefix);
+    let mut lcs = vec![0u32; cells];
+    for i in (prefix..n).rev() {
+        for j in (prefix..m).rev() {
+            lcs[at(i, j)] = if same_signature(&a[i], &b[j]) {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (prefix, prefix);
+    while i < n && j < m {
+        if same_signature(&a[i], &b[j]) {
+            push_aligned(&mut diffs, &a[i], &b[j], i, j);
+            i += 1;
+            j += 1;
+        } else if lcs[at(i + 1, j)] >= lcs[at(i, j + 1)] {
+            diffs.push(ScriptDiff::Deleted {
+                index_a: i,
+                event: a[i].clone(),
+            });
+            i += 1;
+        } else {
+            diffs.push(ScriptDiff::Inserted {
+                index_b: j,
+                event: b[j].clone(),
+            });
+            j += 1;
+        }
+    }
+    diffs.extend((i..n).map(|index_a| ScriptDiff::Deleted {
+        index_a,
+        event: a[index_a].clone(),
+    }));
+    diffs.extend((j..m).map(|index_b| ScriptDiff::Inserted {
+        index_b,
+        event: b[index_b].clone(),
+    }));
+
+    for k in 0..suffix {
+        push_aligned(&mut diffs, &a[n + k], &b[m + k], n + k, m + k);
+    }
+    Ok(diffs)
+}
+
+/// Non-delay events, each with the total delay recorded before it, plus the trailing delay
+fn timed_actions(events: &[ScriptEvent]) -> (Vec<(u64, &ScriptEvent)>, u64) {
+    let mut actions = Vec::new();
+    let mut pending_ms = 0;
+    for event in events {
+        match event {
+            ScriptEvent::Delay { duration_ms } => pending_ms += duration_ms,
+            _ => {
+                actions.push((pending_ms, event));
+                pending_ms = 0;
+            }
+        }
+    }
+    (actions, pending_ms)
+}
+
+/// Copy the reference's timing onto `source`, pairing events by position
+/// The n-th non-delay event of each side pairs up; where both are the same kind of event,
+/// the source's delay before it is replaced by the reference's. Events past the shorter
+/// list keep their own timing. Consecutive delays come out merged into one.
+pub fn align_timing(source: &[ScriptEvent], reference: &[ScriptEvent]) -> Vec<ScriptEvent> {
+    let (source_actions, source_tail_ms) = timed_actions(source);
+    let (reference_actions, reference_tail_ms) = timed_actions(reference);
+
+    let mut out = Vec::with_capacity(source.len());
+    let push_delay = |out: &mut Vec<ScriptEvent>, duration_ms: u64| {
+        if duration_ms > 0 {
+            out.push(ScriptEvent::Delay { duration_ms });
+        }
+    };
+    for (index, (delay_ms, event)) in source_actions.iter().enumerate() {
+        let delay_ms = match reference_actions.get(index) {
+            Some((reference_ms, reference_event))
+                if event_type_name(event) == event_type_name(reference_event) =>
+            {
+                *reference_ms
+            }
+            _ => *delay_ms,
+        };
+        push_delay(&mut out, delay_ms);
+        out.push((*event).clone());
+    }
+    let tail_ms = if source_actions.len() == reference_actions.len() {
+        reference_tail_ms
+    } else {
+        source_tail_ms
+    };
+    push_delay(&mut out, tail_ms);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::KeyboardKey;
+
+    fn press(c: char) -> ScriptEvent {
+        ScriptEvent::KeyPress {
+            key: KeyboardKey::Char(c),
+            key_code: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_events() {
+        let a = vec![
+            press('a'),
+            ScriptEvent::Delay { duration_ms: 10 },
+            press('b'),
+        ];
+        let b = vec![
+            press('a'),
+            ScriptEvent::Delay { duration_ms: 20 },
+            press('c'),
+        ];
+        assert_eq!(
+            diff_events(&a, &b).unwrap(),
+            vec![
+                ScriptDiff::DelayChanged {
+                    index_a: 1,
+                    index_b: 1,
+                    from_ms: 10,
+                    to_ms: 20,
+                },
+                ScriptDiff::Deleted {
+                    index_a: 2,
+                    event: press('b'),
+                },
+                ScriptDiff::Inserted {
+                    index_b: 2,
+                    event: press('c'),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_timing() {
+        let click = ScriptEvent::MousePress {
+            button: crate::script::MouseButton::Left,
+            x: 5.0,
+            y: 5.0,
+        };
+        let source = vec![
+            ScriptEvent::Delay { duration_ms: 300 },
+            press('a'),
+            ScriptEvent::Delay { duration_ms: 250 },
+            press('b'),
+            click.clone(),
+            ScriptEvent::Delay { duration_ms: 90 },
+            press('c'),
+        ];
+        let reference = vec![
+            ScriptEvent::Delay { duration_ms: 100 },
+            press('x'),
+            ScriptEvent::Delay { duration_ms: 50 },
+            press('y'),
+            ScriptEvent::Delay { duration_ms: 40 },
+            press('z'),
+        ];
+
+        assert_eq!(
+            align_timing(&source, &reference),
+            vec![
+                ScriptEvent::Delay { duration_ms: 100 },
+                press('a'),
+                ScriptEvent::Delay { duration_ms: 50 },
+                press('b'),
+                // Key press in the reference: timing kept
+                click,
+                ScriptEvent::Delay { duration_ms: 90 },
+                // Past the reference's end
+                press('c'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let a = vec![press('a'), press('b')];
+        assert!(diff_events(&a, &a).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_long_common_ends() {
+        let mut a = vec![ScriptEvent::Delay { duration_ms: 5 }; 10_000];
+        a.push(press('a'));
+        a.extend(vec![press('z'); 10_000]);
+        a.push(ScriptEvent::Delay { duration_ms: 7 });
+        let mut b = a.clone();
+        b[10_000] = press('b');
+        b[20_001] = ScriptEvent::Delay { duration_ms: 9 };
+
+        assert_eq!(
+            diff_events(&a, &b).unwrap(),
+            vec![
+                ScriptDiff::Deleted {
+                    index_a: 10_000,
+                    event: press('a'),
+                },
+                ScriptDiff::Inserted {
+                    index_b: 10_000,
+                    event: press('b'),
+                },
+                ScriptDiff::DelayChanged {
+                    index_a: 20_001,
+                    index_b: 20_001,
+                    from_ms: 7,
+                    to_ms: 9,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_refuses_huge_middle() {
+        let a = vec![press('a'); 5_000];
+        let b = vec![press('b'); 5_000];
+        assert!(diff_events(&a, &b).is_err());
+    }
+}
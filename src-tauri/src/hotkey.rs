@@ -13,18 +13,32 @@ static HOTKEY_STATE: Lazy<Arc<HotkeyState>> = Lazy::new(|| Arc::new(HotkeyState:
 pub struct HotkeyState {
     // Current emergency stop key
     stop_key: Mutex<rdev::Key>,
+    // Key that drops a marker into the recording instead of being recorded
+    marker_key: Mutex<rdev::Key>,
+    // Key that toggles the automation master switch
+    kill_switch_key: Mutex<rdev::Key>,
 }
 
 impl HotkeyState {
     pub fn new() -> Self {
         Self {
             stop_key: Mutex::new(rdev::Key::Escape),
+            marker_key: Mutex::new(rdev::Key::F8),
+            kill_switch_key: Mutex::new(rdev::Key::Pause),
         }
     }
 
     pub fn get_stop_key(&self) -> rdev::Key {
         *self.stop_key.lock()
     }
+
+    pub fn get_marker_key(&self) -> rdev::Key {
+        *self.marker_key.lock()
+    }
+
+    pub fn get_kill_switch_key(&self) -> rdev::Key {
+        *self.kill_switch_key.lock()
+    }
 }
 
 impl Default for HotkeyState {
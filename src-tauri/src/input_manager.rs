@@ -3,22 +3,43 @@
 
 use crate::macro_trigger;
 use crate::player;
-use crate::recorder;
-use crate::script::{KeyboardKey, MouseButton, ScriptEvent};
+use crate::recorder::{self, KeyCaptureMode};
+use crate::script::{physical_key_code, KeyboardKey, MouseButton, Rect, ScriptEvent};
+use crate::settings;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use rdev::{Event, EventType};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager};
 
+/// Payload of the `overlay-style` event consumed by the overlay window
+#[derive(Clone, serde::Serialize)]
+pub struct OverlayStylePayload {
+    pub color: String,
+    pub border_width: u32,
+    pub label: Option<String>,
+}
+
 /// Global input manager state
 static INPUT_MANAGER: Lazy<Arc<InputManager>> = Lazy::new(|| Arc::new(InputManager::new()));
 
 pub struct InputManager {
     is_running: AtomicBool,
     app_handle: Mutex<Option<AppHandle>>,
+    /// Whether either Shift key is currently held
+    shift_held: AtomicBool,
+    /// Keys recorded as resolved characters, so releases match their presses
+    resolved_keys: Mutex<HashMap<rdev::Key, KeyboardKey>>,
+    /// Recent key and button presses on the trigger path and when, for debouncing
+    recent_presses: Mutex<Vec<(EventType, Instant)>>,
+    /// NumLock/CapsLock state, assumed off at startup and flipped on each toggle
+    /// Only used where the OS can't be asked
+    num_lock: AtomicBool,
+    caps_lock: AtomicBool,
 }
 
 impl InputManager {
@@ -26,7 +47,107 @@ impl InputManager {
         Self {
             is_running: AtomicBool::new(false),
             app_handle: Mutex::new(None),
+            shift_held: AtomicBool::new(false),
+            resolved_keys: Mutex::new(HashMap::new()),
+            recent_presses: Mutex::new(Vec::new()),
+            num_lock: AtomicBool::new(false),
+            caps_lock: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether this key or button press lands within `window_ms` of the previous identical
+    /// press (switch chatter), whether or not a release came in between
+    fn is_bounce(&self, event_type: &EventType, window_ms: u64) -> bool {
+        self.is_bounce_at(event_type, window_ms, Instant::now())
+    }
+
+    fn is_bounce_at(&self, event_type: &EventType, window_ms: u64, now: Instant) -> bool {
+        if !matches!(
+            event_type,
+            EventType::KeyPress(_) | EventType::ButtonPress(_)
+        ) {
+            return false;
+        }
+
+        let window = Duration::from_millis(window_ms);
+        let mut recent = self.recent_presses.lock();
+        recent.retain(|(_, at)| now.duration_since(*at) < window);
+        // Each press restarts the window, so a burst of chatter is dropped as a whole
+        match recent.iter_mut().find(|(prev, _)| prev == event_type) {
+            Some((_, at)) => {
+                *at = now;
+                true
+            }
+            None => {
+                recent.push((*event_type, now));
+                false
+            }
+        }
+    }
+
+    /// Track Shift state from raw key events
+    fn update_modifiers(&self, event_type: &EventType) {
+        match event_type {
+            EventType::KeyPress(rdev::Key::ShiftLeft | rdev::Key::ShiftRight) => {
+                self.shift_held.store(true, Ordering::SeqCst)
+            }
+            EventType::KeyRelease(rdev::Key::ShiftLeft | rdev::Key::ShiftRight) => {
+                self.shift_held.store(false, Ordering::SeqCst)
+            }
+            // Toggle on release: holding the key auto-repeats presses but releases once
+            EventType::KeyRelease(rdev::Key::NumLock) => {
+                self.num_lock.fetch_xor(true, Ordering::SeqCst);
+            }
+            EventType::KeyRelease(rdev::Key::CapsLock) => {
+                self.caps_lock.fetch_xor(true, Ordering::SeqCst);
+            }
+            _ => {}
+        }
+    }
+
+    /// Convert a key event to the key to record for the given capture mode
+    /// Returns None for keys that should not be recorded (Shift in resolved mode)
+    fn resolve_key(
+        &self,
+        key: rdev::Key,
+        name: Option<&str>,
+        pressed: bool,
+        mode: KeyCaptureMode,
+    ) -> Option<KeyboardKey> {
+        if mode == KeyCaptureMode::Raw {
+            return Some(KeyboardKey::from(key));
+        }
+        if matches!(key, rdev::Key::ShiftLeft | rdev::Key::ShiftRight) {
+            return None;
+        }
+
+        if !pressed {
+            let resolved = self.resolved_keys.lock().remove(&key);
+            return Some(resolved.unwrap_or_else(|| KeyboardKey::from(key)));
         }
+
+        let base = KeyboardKey::from(key);
+        let KeyboardKey::Char(c) = base else {
+            return Some(base);
+        };
+
+        // Prefer the layout-aware character reported by the OS, fall back to a US layout table
+        let produced = name.and_then(|n| {
+            let mut chars = n.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) if !ch.is_control() => Some(ch),
+                _ => None,
+            }
+        });
+        let resolved = match produced {
+            Some(ch) => ch,
+            None if self.shift_held.load(Ordering::SeqCst) => shifted_char(c),
+            None => c,
+        };
+
+        let resolved = KeyboardKey::Char(resolved);
+        self.resolved_keys.lock().insert(key, resolved.clone());
+        Some(resolved)
     }
 
     pub fn set_app_handle(&self, handle: AppHandle) {
@@ -55,18 +176,109 @@ pub fn init(app_handle: AppHandle) {
             // println!("{:?}", event); // Debug print removed
             handle_event(event, manager);
         }) {
-            eprintln!("Input listener error: {:?}", error);
+            log::error!("Input listener error: {:?}", error);
+        }
+    });
+
+    spawn_overlay_watchdog();
+    spawn_overlay_status_ticker();
+}
+
+/// Progress shown on the overlay HUD
+#[derive(Clone, serde::Serialize)]
+pub struct OverlayStatus {
+    pub elapsed_ms: u64,
+    /// Events recorded so far, or the index of the event being played
+    pub event_count: usize,
+    /// Current loop iteration (playback only)
+    pub current_loop: Option<u32>,
+}
+
+/// Emit `overlay-status` twice a second while recording or playing
+fn spawn_overlay_status_ticker() {
+    thread::spawn(|| loop {
+        thread::sleep(Duration::from_millis(500));
+
+        let status = if recorder::is_recording() {
+            let (elapsed_ms, event_count) = recorder::get_state().status();
+            OverlayStatus {
+                elapsed_ms,
+                event_count,
+                current_loop: None,
+            }
+        } else if player::is_playing() {
+            let (elapsed_ms, event_count, current_loop) = player::get_state().progress();
+            OverlayStatus {
+                elapsed_ms,
+                event_count,
+                current_loop: Some(current_loop),
+            }
+        } else {
+            continue;
+        };
+        emit_event("overlay-status", status);
+    });
+}
+
+/// Hide the overlay if it stays visible for over a second while neither recording nor playing
+fn spawn_overlay_watchdog() {
+    thread::spawn(|| {
+        let mut idle_since: Option<Instant> = None;
+        loop {
+            thread::sleep(Duration::from_millis(250));
+
+            if recorder::is_recording() || player::is_playing() {
+                idle_since = None;
+                continue;
+            }
+
+            let since = *idle_since.get_or_insert_with(Instant::now);
+            if since.elapsed() < Duration::from_secs(1) {
+                continue;
+            }
+
+            if let Some(handle) = INPUT_MANAGER.app_handle.lock().as_ref() {
+                if let Some(window) = handle.get_webview_window("overlay") {
+                    if window.is_visible().unwrap_or(false) {
+                        force_hide_overlay(handle);
+                    }
+                }
+            }
         }
     });
 }
 
-/// Helper to show overlay with specific color
-pub fn show_overlay(app: &AppHandle, color: &str) {
+/// What the overlay is currently indicating
+#[derive(Debug, Clone, Copy)]
+pub enum OverlayMode {
+    Recording,
+    Playback,
+}
+
+/// Helper to show overlay styled for the given mode using the configured overlay style
+pub fn show_overlay(app: &AppHandle, mode: OverlayMode) {
+    let style = settings::get_settings().overlay;
+    let (color, label) = match mode {
+        OverlayMode::Recording => (style.recording_color, "REC"),
+        OverlayMode::Playback => (style.playback_color, "PLAY"),
+    };
+
     if let Some(window) = app.get_webview_window("overlay") {
+        let topmost = match mode {
+            OverlayMode::Recording => true,
+            OverlayMode::Playback => style.playback_topmost,
+        };
+        let _ = window.set_always_on_top(topmost);
         let _ = window.show();
-
-        let script = format!("document.body.style.borderColor = '{}';", color);
-        let _ = window.eval(&script);
+        let _ = app.emit_to(
+            "overlay",
+            "overlay-style",
+            OverlayStylePayload {
+                color,
+                border_width: style.border_width,
+                label: style.show_label.then(|| label.to_string()),
+            },
+        );
     }
 }
 
@@ -77,11 +289,180 @@ pub fn hide_overlay(app: &AppHandle) {
     }
 }
 
+/// Hide the overlay and make it click-through, regardless of current state
+pub fn force_hide_overlay(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("overlay") {
+        let _ = window.set_ignore_cursor_events(true);
+        let _ = window.hide();
+    }
+}
+
+/// Let clicks pass through the overlay, or let it capture them (e.g. for an on-overlay Stop button)
+pub fn set_overlay_clickthrough(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let window = app
+        .get_webview_window("overlay")
+        .ok_or_else(|| "Overlay window not found".to_string())?;
+    window
+        .set_ignore_cursor_events(enabled)
+        .map_err(|e| format!("Window error: {}", e))
+}
+
+/// Keep the overlay above other windows, or let other windows (e.g. screen recorders) cover it
+pub fn set_overlay_topmost(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let window = app
+        .get_webview_window("overlay")
+        .ok_or_else(|| "Overlay window not found".to_string())?;
+    window
+        .set_always_on_top(enabled)
+        .map_err(|e| format!("Window error: {}", e))
+}
+
+/// Current (NumLock, CapsLock) state
+/// Read from the OS where possible, otherwise tracked from the toggles seen since startup
+pub fn lock_state() -> (bool, bool) {
+    let (num_lock, caps_lock) = os_lock_state();
+    (
+        num_lock.unwrap_or_else(|| INPUT_MANAGER.num_lock.load(Ordering::SeqCst)),
+        caps_lock.unwrap_or_else(|| INPUT_MANAGER.caps_lock.load(Ordering::SeqCst)),
+    )
+}
+
+/// Current (NumLock, CapsLock) state read from the OS; None where it can't be read
+#[cfg(target_os = "windows")]
+fn os_lock_state() -> (Option<bool>, Option<bool>) {
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetKeyState(virt_key: i32) -> i16;
+    }
+    const VK_NUMLOCK: i32 = 0x90;
+    const VK_CAPITAL: i32 = 0x14;
+    // The low bit is the key's toggle state
+    let toggled = |key| unsafe { GetKeyState(key) } & 1 != 0;
+    (Some(toggled(VK_NUMLOCK)), Some(toggled(VK_CAPITAL)))
+}
+
+/// Current (NumLock, CapsLock) state read from the OS; Macs have no NumLock
+#[cfg(target_os = "macos")]
+fn os_lock_state() -> (Option<bool>, Option<bool>) {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceFlagsState(state_id: i32) -> u64;
+    }
+    const HID_SYSTEM_STATE: i32 = 1;
+    const ALPHA_SHIFT_MASK: u64 = 0x0001_0000;
+    let flags = unsafe { CGEventSourceFlagsState(HID_SYSTEM_STATE) };
+    (None, Some(flags & ALPHA_SHIFT_MASK != 0))
+}
+
+/// Lock key state can't be read here yet, so both fall back to toggle tracking
+#[cfg(all(unix, not(target_os = "macos")))]
+fn os_lock_state() -> (Option<bool>, Option<bool>) {
+    (None, None)
+}
+
+/// Scale factor of the monitor showing the main window, falling back to the primary monitor
+pub fn display_scale_factor() -> f64 {
+    let handle = INPUT_MANAGER.app_handle.lock();
+    let Some(handle) = handle.as_ref() else {
+        return 1.0;
+    };
+
+    let monitor = match handle.get_webview_window("main") {
+        Some(window) => window.current_monitor().ok().flatten(),
+        None => None,
+    };
+    monitor
+        .or_else(|| handle.primary_monitor().ok().flatten())
+        .map(|m| m.scale_factor())
+        .unwrap_or(1.0)
+}
+
+fn to_rect(position: &tauri::PhysicalPosition<i32>, size: &tauri::PhysicalSize<u32>) -> Rect {
+    Rect {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    }
+}
+
+/// Work area of the primary monitor (the screen minus taskbar or dock)
+pub fn work_area() -> Result<Rect, String> {
+    let handle = INPUT_MANAGER.app_handle.lock();
+    let handle = handle.as_ref().ok_or("App not initialized")?;
+    let monitor = handle
+        .primary_monitor()
+        .map_err(|e| format!("Monitor error: {}", e))?
+        .ok_or("No primary monitor")?;
+    let area = monitor.work_area();
+    Ok(to_rect(&area.position, &area.size))
+}
+
+/// Bounding box of every monitor, or None if monitors can't be queried
+pub fn screen_bounds() -> Option<Rect> {
+    let handle = INPUT_MANAGER.app_handle.lock();
+    let monitors = handle.as_ref()?.available_monitors().ok()?;
+    monitors
+        .iter()
+        .map(|m| to_rect(m.position(), m.size()))
+        .reduce(|a, b| a.union(&b))
+}
+
+/// Title and top-left corner of the foreground window, or None if it can't be queried
+pub fn foreground_window() -> Option<(String, f64, f64)> {
+    let window = active_win_pos_rs::get_active_window().ok()?;
+    Some((window.title, window.position.x, window.position.y))
+}
+
 /// Emit an event to the frontend
 pub fn emit_event(event_name: &str, payload: impl serde::Serialize + Clone) {
     INPUT_MANAGER.emit_event(event_name, payload);
 }
 
+/// End a recording from a hotkey, restoring the windows like the stop command
+fn stop_recording_from_key() {
+    if !recorder::get_state().stop() {
+        return;
+    }
+    if let Some(handle) = INPUT_MANAGER.app_handle.lock().as_ref() {
+        hide_overlay(handle);
+        if let Some(window) = handle.get_webview_window("main") {
+            let _ = window.show();
+        }
+    }
+    emit_event(
+        "hotkey-event",
+        crate::hotkey::HotkeyEvent {
+            action: "recording-stopped".to_string(),
+            recording: false,
+            playing: false,
+        },
+    );
+}
+
+/// Flip the automation master switch; turning it off also ends playback and recording
+pub fn set_automation_enabled(enabled: bool) -> Result<(), String> {
+    settings::set_automation_enabled(enabled)?;
+    if !enabled {
+        player::stop_playback();
+        stop_recording_from_key();
+    }
+    emit_event(
+        "hotkey-event",
+        crate::hotkey::HotkeyEvent {
+            action: if enabled {
+                "automation-enabled"
+            } else {
+                "automation-disabled"
+            }
+            .to_string(),
+            recording: recorder::is_recording(),
+            playing: enabled && player::is_playing(),
+        },
+    );
+    Ok(())
+}
+
 /// Called by player when playback finishes naturally
 pub fn on_playback_finish() {
     let manager = &INPUT_MANAGER;
@@ -94,14 +475,44 @@ pub fn on_playback_finish() {
     }
 }
 
-fn handle_event(event: Event, _manager: &InputManager) {
-    // 1. Handle Global Hotkeys (Emergency Stop)
+/// Character produced by Shift + `c` on a US layout
+fn shifted_char(c: char) -> char {
+    match c {
+        '1' => '!',
+        '2' => '@',
+        '3' => '#',
+        '4' => '$',
+        '5' => '%',
+        '6' => '^',
+        '7' => '&',
+        '8' => '*',
+        '9' => '(',
+        '0' => ')',
+        _ => c.to_ascii_uppercase(),
+    }
+}
+
+fn handle_event(event: Event, manager: &InputManager) {
+    manager.update_modifiers(&event.event_type);
+
+    // 1. Handle Global Hotkeys (Kill Switch, Emergency Stop)
     let hotkey_state = crate::hotkey::get_state();
+    match event.event_type {
+        EventType::KeyPress(key) if key == hotkey_state.get_kill_switch_key() => {
+            let enabled = !settings::get_settings().automation_enabled;
+            if let Err(e) = set_automation_enabled(enabled) {
+                log::error!("Failed to toggle automation: {}", e);
+            }
+            return;
+        }
+        EventType::KeyRelease(key) if key == hotkey_state.get_kill_switch_key() => return,
+        _ => {}
+    }
     if let EventType::KeyPress(key) = event.event_type {
         if key == hotkey_state.get_stop_key() {
             if player::is_playing() {
                 player::stop_playback();
-                let _ = _manager.app_handle.lock().as_ref().map(|app| {
+                let _ = manager.app_handle.lock().as_ref().map(|app| {
                     let _ = app.get_webview_window("main").map(|w| {
                         let _ = w.show();
                         let _ = w.set_focus();
@@ -123,30 +534,76 @@ fn handle_event(event: Event, _manager: &InputManager) {
 
     // 2. Playback Protection (Skip normal event processing if playing)
     if player::is_playing() {
-        // Still check for task-specific stop keys via TaskState
+        // Still check for task-specific stop keys, and triggers on passthrough keys
         if let EventType::KeyPress(key) = event.event_type {
-            if macro_trigger::get_state().check_key_event(&KeyboardKey::from(key)) {
-                return;
+            let key = KeyboardKey::from(key);
+            let task_state = macro_trigger::get_state();
+            if !task_state.check_stop_key(&key)
+                && settings::get_settings()
+                    .playback_passthrough_keys
+                    .contains(&key)
+            {
+                task_state.check_key_event(&key);
             }
         }
         return;
     }
 
-    // 3. Handle Recording
-    if recorder::is_recording() {
+    // 3. Handle Recording (frontend-only recordings are fed by the main window instead)
+    if recorder::is_recording() && !recorder::get_state().is_frontend_only() {
+        // The marker key is an annotation, not a keystroke
+        match event.event_type {
+            EventType::KeyPress(key) if key == hotkey_state.get_marker_key() => {
+                recorder::get_state().add_marker();
+                return;
+            }
+            EventType::KeyRelease(key) if key == hotkey_state.get_marker_key() => return,
+            _ => {}
+        }
+
         let elapsed = recorder::get_state().get_elapsed_ms();
+        let options = recorder::get_state().options();
+        if let EventType::KeyPress(key) = event.event_type {
+            if options.stop_key.as_ref() == Some(&KeyboardKey::from(key)) {
+                stop_recording_from_key();
+                return;
+            }
+        }
+        // Anchor mouse events to the window under them, noting when that window changes
+        let note_window = || {
+            if options.window_relative {
+                recorder::get_state().note_window(foreground_window());
+            }
+        };
+        let key_mode = options.key_mode;
+        // Resolved characters fold in Shift, so the bare physical key wouldn't reproduce them
+        let key_code = |key| match key_mode {
+            KeyCaptureMode::Raw => physical_key_code(key),
+            KeyCaptureMode::Resolved => None,
+        };
         match event.event_type {
-            EventType::KeyPress(key) => {
-                recorder::get_state().commit_event(ScriptEvent::KeyPress {
-                    key: KeyboardKey::from(key),
-                });
+            EventType::KeyPress(raw) => {
+                if let Some(key) = manager.resolve_key(raw, event.name.as_deref(), true, key_mode) {
+                    let press = ScriptEvent::KeyPress {
+                        key,
+                        key_code: key_code(raw),
+                    };
+                    if !recorder::get_state().is_duplicate_key_press(&press) {
+                        recorder::get_state().commit_event(press);
+                    }
+                }
             }
-            EventType::KeyRelease(key) => {
-                recorder::get_state().commit_event(ScriptEvent::KeyRelease {
-                    key: KeyboardKey::from(key),
-                });
+            EventType::KeyRelease(raw) => {
+                if let Some(key) = manager.resolve_key(raw, None, false, key_mode) {
+                    recorder::get_state().note_key_release(&key);
+                    recorder::get_state().commit_event(ScriptEvent::KeyRelease {
+                        key,
+                        key_code: key_code(raw),
+                    });
+                }
             }
             EventType::ButtonPress(button) => {
+                note_window();
                 let (x, y) = recorder::get_state().get_mouse_position();
                 recorder::get_state().commit_event(ScriptEvent::MousePress {
                     button: MouseButton::from(button),
@@ -155,6 +612,7 @@ fn handle_event(event: Event, _manager: &InputManager) {
                 });
             }
             EventType::ButtonRelease(button) => {
+                note_window();
                 let (x, y) = recorder::get_state().get_mouse_position();
                 recorder::get_state().commit_event(ScriptEvent::MouseRelease {
                     button: MouseButton::from(button),
@@ -166,19 +624,67 @@ fn handle_event(event: Event, _manager: &InputManager) {
                 recorder::get_state().update_mouse_position(x, y);
                 // Throttle mouse move recording: ONLY record if time >= 20ms
                 if elapsed >= 20 {
-                    recorder::get_state().commit_event(ScriptEvent::MouseMove { x, y });
+                    if options.relative_mouse {
+                        if let Some((dx, dy)) = recorder::get_state().relative_delta(x, y) {
+                            recorder::get_state()
+                                .commit_event(ScriptEvent::MouseMoveRel { dx, dy });
+                        }
+                    } else {
+                        note_window();
+                        recorder::get_state().commit_event(ScriptEvent::MouseMove { x, y });
+                    }
                 }
             }
             EventType::Wheel { delta_x, delta_y } => {
-                recorder::get_state().commit_event(ScriptEvent::MouseScroll { delta_x, delta_y });
+                // Relative recordings have no meaningful absolute position to scroll at
+                let (x, y) = if options.relative_mouse {
+                    (None, None)
+                } else {
+                    note_window();
+                    let (x, y) = recorder::get_state().get_mouse_position();
+                    (Some(x), Some(y))
+                };
+                recorder::get_state().commit_event(ScriptEvent::MouseScroll {
+                    delta_x,
+                    delta_y,
+                    x,
+                    y,
+                });
             }
         }
     }
 
     // 4. Handle Tasks (Triggers)
     if macro_trigger::get_state().is_active() && !recorder::is_recording() {
+        let debounce_ms = settings::get_settings().trigger_debounce_ms;
+        if manager.is_bounce(&event.event_type, debounce_ms) {
+            return;
+        }
         if let EventType::KeyPress(key) = event.event_type {
             macro_trigger::get_state().check_key_event(&KeyboardKey::from(key));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounce_spans_release() {
+        let manager = InputManager::new();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let press = EventType::ButtonPress(rdev::Button::Left);
+        let release = EventType::ButtonRelease(rdev::Button::Left);
+
+        assert!(!manager.is_bounce_at(&press, 30, at(0)));
+        assert!(!manager.is_bounce_at(&release, 30, at(5)));
+        // Chatter: press, release, press inside the window
+        assert!(manager.is_bounce_at(&press, 30, at(10)));
+        // A different key is timed on its own
+        assert!(!manager.is_bounce_at(&EventType::KeyPress(rdev::Key::KeyA), 30, at(12)));
+        assert!(!manager.is_bounce_at(&press, 30, at(100)));
+        assert!(!manager.is_bounce_at(&press, 0, at(100)));
+    }
+}
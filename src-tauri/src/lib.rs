@@ -1,16 +1,34 @@
 //! AutoKB - Desktop Automation Application
 //! Main Tauri entry point with all commands
 
+mod ahk;
+mod analysis;
+mod bundle;
+mod cancel;
+mod capabilities;
+mod chord;
+mod diff;
 mod hotkey;
 mod input_manager;
 mod macro_trigger;
+mod pixel;
 mod player;
 mod recorder;
+mod scheduler;
 mod script;
+mod settings;
+mod simplify;
+mod transform;
 
-use script::{KeyboardKey, LoopConfig, Script, ScriptEvent, Task};
+use input_manager::OverlayMode;
+use script::{
+    KeyboardKey, LenientScript, LoopConfig, MouseButton, Script, ScriptEvent, SplitMode, Task,
+};
+use settings::{AppSettings, OverlayStyle};
 use std::fs;
 use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
 use tauri::Manager;
 use tauri::{WebviewUrl, WebviewWindowBuilder};
 
@@ -33,31 +51,101 @@ fn release_overlay_window(app: tauri::AppHandle) {
     }
 }
 
+/// Toggle whether the overlay lets clicks through to the windows below
+/// While disabled the overlay blocks clicks on everything it covers
+#[tauri::command]
+fn set_overlay_clickthrough(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    input_manager::set_overlay_clickthrough(&app, enabled)
+}
+
+/// Toggle whether the overlay stays above other windows, for the current overlay only
+/// Use the overlay style's `playback_topmost` to change it for every playback
+#[tauri::command]
+fn set_overlay_topmost(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    input_manager::set_overlay_topmost(&app, enabled)
+}
+
+/// Recover from a stuck overlay by hiding it unconditionally
+#[tauri::command]
+fn force_hide_overlay(app: tauri::AppHandle) {
+    input_manager::force_hide_overlay(&app);
+}
+
 // ============================================================================
 // Recording Commands
 // ============================================================================
 
 /// Start recording keyboard/mouse events
 #[tauri::command]
-fn start_recording(app: tauri::AppHandle) -> Result<(), String> {
+fn start_recording(
+    app: tauri::AppHandle,
+    options: Option<recorder::RecordingOptions>,
+) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.hide();
     }
-    input_manager::show_overlay(&app, "#f85149");
-    recorder::start_recording()
+    input_manager::show_overlay(&app, OverlayMode::Recording);
+    recorder::start_recording(options.unwrap_or_default())
+}
+
+/// Start recording until `stop_key` is pressed (the key itself is not recorded)
+/// Emits `hotkey-event` with action `recording-stopped`; fetch the result with get_last_recording
+#[tauri::command]
+fn start_recording_until(
+    app: tauri::AppHandle,
+    stop_key: KeyboardKey,
+    options: Option<recorder::RecordingOptions>,
+) -> Result<(), String> {
+    let options = recorder::RecordingOptions {
+        stop_key: Some(stop_key),
+        ..options.unwrap_or_default()
+    };
+    start_recording(app, Some(options))
 }
 
 /// Stop recording and return recorded events
 #[tauri::command]
 fn stop_recording(app: tauri::AppHandle) -> Vec<ScriptEvent> {
-    input_manager::hide_overlay(&app);
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.show();
-        let _ = window.set_focus();
+    // A second stop (e.g. from both the hotkey and the UI) leaves the windows alone
+    if recorder::is_recording() {
+        input_manager::hide_overlay(&app);
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
     }
     recorder::stop_recording()
 }
 
+/// Record for a fixed duration, then stop and return the recorded events
+/// Emits `recording-countdown` with the whole seconds remaining
+#[tauri::command(async)]
+fn start_recording_timed(
+    app: tauri::AppHandle,
+    duration_ms: u64,
+) -> Result<Vec<ScriptEvent>, String> {
+    start_recording(app.clone(), None)?;
+
+    let deadline = Instant::now() + Duration::from_millis(duration_ms);
+    let mut last_emitted = None;
+    while recorder::is_recording() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+
+        let seconds = remaining.as_millis().div_ceil(1000) as u64;
+        if last_emitted != Some(seconds) {
+            input_manager::emit_event("recording-countdown", seconds);
+            last_emitted = Some(seconds);
+        }
+        thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+
+    input_manager::emit_event("recording-countdown", 0u64);
+    Ok(stop_recording(app))
+}
+
 /// Check if currently recording
 #[tauri::command]
 fn is_recording() -> bool {
@@ -70,24 +158,120 @@ fn get_recorded_events() -> Vec<ScriptEvent> {
     recorder::get_recorded_events()
 }
 
+/// Get the events of the last finished recording without clearing them
+#[tauri::command]
+fn get_last_recording() -> Vec<ScriptEvent> {
+    recorder::get_last_recording()
+}
+
+/// Display scale factor captured when the last recording started
+#[tauri::command]
+fn get_recording_scale_factor() -> f64 {
+    recorder::recording_scale_factor()
+}
+
+/// Start recording from the focused main window only, without the global input hook
+/// The frontend sends events through `record_frontend_event`; the main window stays visible
+#[tauri::command]
+fn start_frontend_recording(options: Option<recorder::RecordingOptions>) -> Result<(), String> {
+    recorder::start_frontend_recording(options.unwrap_or_default())
+}
+
+/// Stop a frontend-only recording and return the recorded events
+#[tauri::command]
+fn stop_frontend_recording() -> Vec<ScriptEvent> {
+    recorder::stop_recording()
+}
+
+/// Discard the events recorded so far while the recording keeps running
+#[tauri::command]
+fn clear_recording() -> Result<(), String> {
+    recorder::clear_recording()
+}
+
 /// Record an event from the frontend (for when window is focused)
 #[tauri::command]
 fn record_frontend_event(event: ScriptEvent) {
     recorder::get_state().commit_event(event);
 }
 
+/// Record the text an IME composition committed in the main window
+/// Ignored unless the recording was started with `ime_text`
+#[tauri::command]
+fn record_frontend_composition(text: String) {
+    recorder::get_state().commit_composition(text);
+}
+
 // ============================================================================
 // Playback Commands
 // ============================================================================
 
-/// Play a script
-#[tauri::command]
-fn play_script(app: tauri::AppHandle, script: Script) -> Result<(), String> {
+/// Start playback with the playback windows set up, undoing them if it fails to start
+/// Once running, the player restores the windows itself when playback finishes
+fn start_playback(
+    app: &tauri::AppHandle,
+    show_overlay: Option<bool>,
+    start: impl FnOnce() -> Result<(), String>,
+) -> Result<(), String> {
+    // A rejected start while already playing must leave the running playback's overlay alone
+    let was_playing = player::is_playing();
+    prepare_playback_window(app, show_overlay);
+    let result = start();
+    if result.is_err() && !was_playing {
+        input_manager::on_playback_finish();
+    }
+    result
+}
+
+/// Hide the main window and show the playback overlay, unless the caller opted out
+fn prepare_playback_window(app: &tauri::AppHandle, show_overlay: Option<bool>) {
+    if !show_overlay.unwrap_or(true) {
+        return;
+    }
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.hide();
     }
-    input_manager::show_overlay(&app, "#58a6ff");
-    player::play_script(script)
+    input_manager::show_overlay(app, OverlayMode::Playback);
+}
+
+/// Play a script
+/// Pass `show_overlay: false` to keep the main window and skip the overlay
+/// Infinite loops are refused unless `confirm_infinite` is set
+#[tauri::command]
+fn play_script(
+    app: tauri::AppHandle,
+    script: Script,
+    show_overlay: Option<bool>,
+    confirm_infinite: Option<bool>,
+) -> Result<(), String> {
+    player::ensure_infinite_confirmed(&script.loop_config, confirm_infinite)?;
+    start_playback(&app, show_overlay, || player::play_script(script))
+}
+
+/// Load a script file and play it with its own stored loop configuration and speed
+#[tauri::command]
+fn play_saved_script(
+    app: tauri::AppHandle,
+    path: String,
+    show_overlay: Option<bool>,
+    confirm_infinite: Option<bool>,
+) -> Result<(), String> {
+    let script = Script::load(&path)?;
+    script.verify_checksum()?;
+    player::ensure_infinite_confirmed(&script.loop_config, confirm_infinite)?;
+    start_playback(&app, show_overlay, || player::play_script(script))
+}
+
+/// Play a script and resolve only once playback has finished or been stopped
+#[tauri::command(async)]
+fn play_script_blocking(
+    app: tauri::AppHandle,
+    script: Script,
+    show_overlay: Option<bool>,
+    confirm_infinite: Option<bool>,
+) -> Result<(), String> {
+    player::ensure_infinite_confirmed(&script.loop_config, confirm_infinite)?;
+    start_playback(&app, show_overlay, || player::play_script_blocking(script))
 }
 
 /// Play a list of events with speed multiplier
@@ -96,12 +280,57 @@ fn play_events(
     app: tauri::AppHandle,
     events: Vec<ScriptEvent>,
     speed_multiplier: f64,
+    loop_config: Option<LoopConfig>,
+    show_overlay: Option<bool>,
+    confirm_infinite: Option<bool>,
 ) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.hide();
+    if let Some(loop_config) = &loop_config {
+        player::ensure_infinite_confirmed(loop_config, confirm_infinite)?;
     }
-    input_manager::show_overlay(&app, "#58a6ff");
-    player::play_events(events, speed_multiplier)
+    start_playback(&app, show_overlay, || {
+        player::play_events(events, speed_multiplier, loop_config)
+    })
+}
+
+/// Type a string, optionally at a typing speed in words per minute
+#[tauri::command]
+fn type_text(
+    app: tauri::AppHandle,
+    text: String,
+    wpm: Option<f64>,
+    show_overlay: Option<bool>,
+) -> Result<(), String> {
+    start_playback(&app, show_overlay, || player::type_text(&text, wpm))
+}
+
+/// Animate a script's cursor path on the overlay without sending any input
+#[tauri::command]
+fn visualize_script(app: tauri::AppHandle, script: Script) -> Result<(), String> {
+    start_playback(&app, None, || player::visualize_script(script))
+}
+
+/// Execute a single event immediately (for verifying it while editing)
+#[tauri::command]
+fn execute_single_event(event: ScriptEvent) -> Result<(), String> {
+    player::execute_single_event(event)
+}
+
+/// Current cursor position, for calibrating offsets against a recording
+#[tauri::command]
+fn get_cursor_position() -> Result<(i32, i32), String> {
+    player::cursor_position()
+}
+
+/// Work area of the primary monitor in physical pixels
+#[tauri::command]
+fn get_work_area() -> Result<script::Rect, String> {
+    input_manager::work_area()
+}
+
+/// Special key names playback supports, for building key pickers
+#[tauri::command]
+fn list_special_keys() -> Vec<String> {
+    player::special_key_names()
 }
 
 /// Stop playback
@@ -115,19 +344,127 @@ fn stop_playback(app: tauri::AppHandle) {
     player::stop_playback()
 }
 
+/// Start a script in step mode, paused before its first event
+/// Infinite loops are refused unless `confirm_infinite` is set
+#[tauri::command]
+fn play_script_stepping(
+    app: tauri::AppHandle,
+    script: Script,
+    show_overlay: Option<bool>,
+    confirm_infinite: Option<bool>,
+) -> Result<(), String> {
+    player::ensure_infinite_confirmed(&script.loop_config, confirm_infinite)?;
+    start_playback(&app, show_overlay, || player::play_script_stepping(script))
+}
+
+/// Run the next event of a step-mode playback
+#[tauri::command]
+fn step_playback() -> Result<(), String> {
+    player::step_playback()
+}
+
+/// Loop iterations the running playback has left after the current one (null if infinite)
+#[tauri::command]
+fn get_remaining_loops() -> Result<Option<u32>, String> {
+    player::get_remaining_loops()
+}
+
+/// Change how many more loop iterations the running playback does
+#[tauri::command]
+fn set_remaining_loops(count: u32) -> Result<(), String> {
+    player::set_remaining_loops(count)
+}
+
+/// Where playback is: elapsed time, index of the current event and loop iteration
+#[tauri::command]
+fn get_playback_progress() -> PlaybackProgress {
+    let state = player::get_state();
+    let (elapsed_ms, event_index, current_loop) = state.progress();
+    PlaybackProgress {
+        playing: state.is_playing(),
+        stepping: state.is_stepping(),
+        elapsed_ms,
+        event_index,
+        current_loop,
+    }
+}
+
+/// Abort playback and any other cancellable operation in progress
+#[tauri::command]
+fn cancel_all(app: tauri::AppHandle) {
+    input_manager::hide_overlay(&app);
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    cancel::cancel_all();
+}
+
 /// Check if currently playing
 #[tauri::command]
 fn is_playing() -> bool {
     player::is_playing()
 }
 
+// ============================================================================
+// Schedule Commands
+// ============================================================================
+
+/// Schedule a script to play at a given time (milliseconds since the Unix epoch)
+/// Infinite loops are refused unless `confirm_infinite` is set: they would run unattended
+/// and hold up every later scheduled job until stopped
+#[tauri::command]
+fn schedule_playback(
+    script: Script,
+    at_unix_ms: u64,
+    confirm_infinite: Option<bool>,
+) -> Result<scheduler::ScheduledJob, String> {
+    if script.events.is_empty() {
+        return Err("Script has no events".to_string());
+    }
+    player::ensure_infinite_confirmed(&script.loop_config, confirm_infinite)?;
+    Ok(scheduler::schedule_playback(script, at_unix_ms))
+}
+
+/// List pending scheduled playbacks
+#[tauri::command]
+fn list_scheduled() -> Vec<scheduler::ScheduledJob> {
+    scheduler::list_scheduled()
+}
+
+/// Schedule a saved script to play every `interval_ms`, optionally `count` times
+/// An infinitely looping script needs `confirm_infinite`, which is checked again at each run
+#[tauri::command]
+fn schedule_recurring(
+    script_path: String,
+    interval_ms: u64,
+    count: Option<u32>,
+    confirm_infinite: Option<bool>,
+) -> Result<scheduler::RecurringJob, String> {
+    scheduler::schedule_recurring(script_path, interval_ms, count, confirm_infinite)
+}
+
+/// List recurring playbacks with their next run time
+#[tauri::command]
+fn list_recurring() -> Vec<scheduler::RecurringJob> {
+    scheduler::list_recurring()
+}
+
+/// Cancel a scheduled (one-shot or recurring) playback
+#[tauri::command]
+fn cancel_scheduled(id: String) -> Result<(), String> {
+    scheduler::cancel_scheduled(&id)
+}
+
 // ============================================================================
 // Script File Commands
 // ============================================================================
 
 /// Save script to file
 #[tauri::command]
-fn save_script(script: Script, path: String) -> Result<(), String> {
+fn save_script(mut script: Script, path: String) -> Result<(), String> {
+    script.assign_event_ids();
+    script.checksum = Some(script.compute_checksum()?);
     let json =
         serde_json::to_string_pretty(&script).map_err(|e| format!("Serialization error: {}", e))?;
     fs::write(&path, json).map_err(|e| format!("File write error: {}", e))?;
@@ -135,14 +472,37 @@ fn save_script(script: Script, path: String) -> Result<(), String> {
 }
 
 /// Load script from file
+/// Fails with an "Integrity error" if the file was corrupted, unless `ignore_integrity` is set
 #[tauri::command]
-fn load_script(path: String) -> Result<Script, String> {
-    let content = fs::read_to_string(&path).map_err(|e| format!("File read error: {}", e))?;
-    let script: Script =
-        serde_json::from_str(&content).map_err(|e| format!("Parse error: {}", e))?;
+fn load_script(path: String, ignore_integrity: Option<bool>) -> Result<Script, String> {
+    let script = Script::load(&path)?;
+    if !ignore_integrity.unwrap_or(false) {
+        script.verify_checksum()?;
+    }
     Ok(script)
 }
 
+/// Load a script file, skipping events this version can't parse
+#[tauri::command]
+fn load_script_lenient(path: String) -> Result<LenientScript, String> {
+    Script::load_lenient(&path)
+}
+
+/// Load a bare JSON array of events, as produced by external tools
+#[tauri::command]
+fn load_events(path: String) -> Result<Vec<ScriptEvent>, String> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("File read error: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Parse error: {}", e))
+}
+
+/// Save events as a bare JSON array, without script metadata
+#[tauri::command]
+fn save_events(events: Vec<ScriptEvent>, path: String) -> Result<(), String> {
+    let json =
+        serde_json::to_string_pretty(&events).map_err(|e| format!("Serialization error: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("File write error: {}", e))
+}
+
 /// Delete a script file
 #[tauri::command]
 fn delete_script(path: String) -> Result<(), String> {
@@ -150,24 +510,84 @@ fn delete_script(path: String) -> Result<(), String> {
     Ok(())
 }
 
-/// Get default scripts directory
-#[tauri::command]
-fn get_scripts_dir(app: tauri::AppHandle) -> Result<String, String> {
-    let dir = app
-        .path()
+/// Files kept in the app local data directory
+const TASKS_FILE: &str = "tasks.json";
+const CONFIG_FILE: &str = "config.json";
+const SCHEDULES_FILE: &str = "schedules.json";
+
+/// Log file name (without extension) in the app log directory
+const LOG_FILE_NAME: &str = "autokb";
+
+/// Resolve the app local data directory
+fn data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
         .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app local data dir: {}", e))?
-        .join("scripts");
+        .map_err(|e| format!("Failed to get app local data dir: {}", e))
+}
+
+/// Resolve the scripts directory, creating it if needed
+fn scripts_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = data_dir(app)?.join("scripts");
 
     if !dir.exists() {
         fs::create_dir_all(&dir).map_err(|e| format!("Failed to create directory: {}", e))?;
     }
+    Ok(dir)
+}
 
-    dir.to_str()
+fn path_string(path: PathBuf) -> Result<String, String> {
+    path.to_str()
         .map(|s| s.to_string())
         .ok_or_else(|| "Invalid path".to_string())
 }
 
+/// Get default scripts directory
+#[tauri::command]
+fn get_scripts_dir(app: tauri::AppHandle) -> Result<String, String> {
+    path_string(scripts_dir(&app)?)
+}
+
+/// Resolve the log file written by the log plugin
+fn log_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_log_dir()
+        .map(|dir| dir.join(format!("{}.log", LOG_FILE_NAME)))
+        .map_err(|e| format!("Failed to get app log dir: {}", e))
+}
+
+/// Get the path of the log file, for attaching to bug reports
+#[tauri::command]
+fn get_log_path(app: tauri::AppHandle) -> Result<String, String> {
+    path_string(log_path(&app)?)
+}
+
+#[derive(serde::Serialize)]
+struct DataPaths {
+    data_dir: String,
+    scripts_dir: String,
+    config_file: String,
+    tasks_file: String,
+    schedules_file: String,
+    /// Recovery file written by streaming recordings
+    recording_stream_file: String,
+    log_file: String,
+}
+
+/// Report where scripts, settings and tasks are stored, for troubleshooting
+#[tauri::command]
+fn get_data_paths(app: tauri::AppHandle) -> Result<DataPaths, String> {
+    let dir = data_dir(&app)?;
+    Ok(DataPaths {
+        scripts_dir: path_string(scripts_dir(&app)?)?,
+        config_file: path_string(dir.join(CONFIG_FILE))?,
+        tasks_file: path_string(dir.join(TASKS_FILE))?,
+        schedules_file: path_string(dir.join(SCHEDULES_FILE))?,
+        recording_stream_file: path_string(recorder::stream_path())?,
+        log_file: path_string(log_path(&app)?)?,
+        data_dir: path_string(dir)?,
+    })
+}
+
 #[derive(serde::Serialize)]
 struct SavedScript {
     name: String,
@@ -205,14 +625,51 @@ fn list_saved_scripts(app: tauri::AppHandle) -> Result<Vec<SavedScript>, String>
     Ok(scripts)
 }
 
+/// Export all scripts, tasks and settings into a single zip bundle
+#[tauri::command]
+fn export_bundle(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    bundle::export_bundle(&data_dir(&app)?, &PathBuf::from(path))
+}
+
+/// Import a bundle into the app data dir and reload tasks and settings
+#[tauri::command]
+fn import_bundle(
+    app: tauri::AppHandle,
+    path: String,
+    overwrite: bool,
+) -> Result<bundle::ImportSummary, String> {
+    let dir = data_dir(&app)?;
+    let summary = bundle::import_bundle(&dir, &PathBuf::from(path), overwrite)?;
+    macro_trigger::init_storage(dir.join(TASKS_FILE))?;
+    settings::init_storage(dir.join(CONFIG_FILE))?;
+    Ok(summary)
+}
+
+/// Copy the .autokb scripts from another folder into the scripts dir
+#[tauri::command]
+fn import_scripts_from_dir(
+    app: tauri::AppHandle,
+    source: String,
+    on_conflict: bundle::ConflictPolicy,
+) -> Result<bundle::ImportSummary, String> {
+    bundle::import_scripts_from_dir(&PathBuf::from(source), &scripts_dir(&app)?, on_conflict)
+}
+
 // ============================================================================
 // Task Commands
 // ============================================================================
 
 /// Add a new task
+/// Pass `replace_existing: false` to refuse when another task uses the same trigger
+#[tauri::command]
+fn add_task(task: Task, replace_existing: Option<bool>) -> Result<(), String> {
+    macro_trigger::add_task_checked(task, replace_existing.unwrap_or(true))
+}
+
+/// Get the task already bound to a trigger key, if any
 #[tauri::command]
-fn add_task(task: Task) {
-    macro_trigger::add_task(task);
+fn check_trigger_conflict(trigger_key: KeyboardKey) -> Option<Task> {
+    macro_trigger::check_trigger_conflict(&trigger_key)
 }
 
 /// Remove a task by ID
@@ -233,6 +690,30 @@ fn toggle_task(id: String, enabled: bool) {
     macro_trigger::toggle_task(&id, enabled);
 }
 
+/// Set a task's inline events (played instead of its script file)
+#[tauri::command]
+fn set_task_events(id: String, events: Vec<ScriptEvent>) -> Result<Task, String> {
+    macro_trigger::set_task_events(&id, events)
+}
+
+/// How often each task has fired since startup
+#[tauri::command]
+fn get_task_stats() -> Vec<macro_trigger::TaskStat> {
+    macro_trigger::get_task_stats()
+}
+
+/// (trigger, task name) pairs for every task, sorted by trigger to show shared keys
+#[tauri::command]
+fn macro_trigger_map() -> Vec<(String, String)> {
+    macro_trigger::trigger_map()
+}
+
+/// Check that a task's script file still exists and parses
+#[tauri::command]
+fn validate_macro(id: String) -> Result<macro_trigger::MacroValidation, String> {
+    macro_trigger::validate_macro(&id)
+}
+
 /// Start task listener
 #[tauri::command]
 fn start_task_listener() -> Result<(), String> {
@@ -253,25 +734,85 @@ fn create_task_binding(
     stop_key: Option<String>,
     script_path: String,
 ) -> Result<Task, String> {
-    let parse_key = |k: String| {
-        if k.len() == 1 {
-            KeyboardKey::Char(k.chars().next().unwrap())
-        } else {
-            KeyboardKey::Special(k)
-        }
-    };
+    let task = new_task(
+        name,
+        trigger_key.map(|k| k.parse()).transpose()?,
+        stop_key.map(|k| k.parse()).transpose()?,
+        script_path,
+    );
+
+    macro_trigger::add_task(task.clone());
+    Ok(task)
+}
+
+/// Parse a trigger key name the way create_task_binding does, for validating input as it's typed
+#[tauri::command]
+fn parse_trigger(trigger_value: String) -> Result<KeyboardKey, String> {
+    trigger_value.parse()
+}
 
-    let task = Task {
+/// Build an enabled task with default playback settings
+fn new_task(
+    name: String,
+    trigger_key: Option<KeyboardKey>,
+    stop_key: Option<KeyboardKey>,
+    script_path: String,
+) -> Task {
+    Task {
         id: macro_trigger::uuid_simple(),
         name,
         description: String::new(),
-        trigger_key: trigger_key.map(parse_key),
-        stop_key: stop_key.map(parse_key),
+        trigger_key,
+        stop_key,
         script_path,
+        events: None,
         enabled: true,
         loop_config: LoopConfig::default(),
         speed_multiplier: 1.0,
+        requires_lock_state: None,
+    }
+}
+
+/// Save events as a script in the scripts dir and bind a task to it in one step
+#[tauri::command]
+fn save_as_task(
+    app: tauri::AppHandle,
+    name: String,
+    trigger_key: Option<KeyboardKey>,
+    events: Vec<ScriptEvent>,
+) -> Result<Task, String> {
+    let mut task = new_task(name.clone(), trigger_key, None, String::new());
+    // Check before writing anything so a conflict doesn't leave an orphan script
+    macro_trigger::ensure_trigger_free(&task)?;
+
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // A blank name would leave a bare ".autokb", so name the file after the task instead
+    let stem = match stem.trim() {
+        "" => task.id.clone(),
+        trimmed => trimmed.to_string(),
+    };
+    let dir = scripts_dir(&app)?;
+    let mut path = dir.join(format!("{}.autokb", stem));
+    if path.exists() {
+        path = dir.join(format!("{}-{}.autokb", stem, task.id));
+    }
+
+    let script = Script {
+        name,
+        events,
+        ..Default::default()
     };
+    task.script_path = path_string(path)?;
+    save_script(script, task.script_path.clone())?;
 
     macro_trigger::add_task(task.clone());
     Ok(task)
@@ -305,26 +846,350 @@ fn delete_event(mut events: Vec<ScriptEvent>, index: usize) -> Vec<ScriptEvent>
     events
 }
 
+/// Delete the event at index, keeping the other events' ids
+#[tauri::command]
+fn delete_script_event(mut script: Script, index: usize) -> Script {
+    script.remove_event(index);
+    script
+}
+
+/// Insert an event at index with a fresh id
+#[tauri::command]
+fn insert_script_event(mut script: Script, index: usize, event: ScriptEvent) -> Script {
+    script.insert_event(index, event);
+    script
+}
+
+/// Move the event at `from` to `to`, keeping its id
+#[tauri::command]
+fn move_script_event(mut script: Script, from: usize, to: usize) -> Script {
+    script.move_event(from, to);
+    script
+}
+
+/// Give every event of the script an id, e.g. after recording into it
+#[tauri::command]
+fn assign_event_ids(mut script: Script) -> Script {
+    script.assign_event_ids();
+    script
+}
+
+/// Cut a long recording into several scripts
+#[tauri::command]
+fn split_script(script: Script, mode: SplitMode) -> Vec<Script> {
+    script.split(mode)
+}
+
+/// Disable the event at index, or re-enable it if already disabled
+#[tauri::command]
+fn toggle_event(mut events: Vec<ScriptEvent>, index: usize) -> Vec<ScriptEvent> {
+    if index < events.len() {
+        let toggled = match events.remove(index) {
+            ScriptEvent::Disabled { event } => *event,
+            event => ScriptEvent::Disabled {
+                event: Box::new(event),
+            },
+        };
+        events.insert(index, toggled);
+    }
+    events
+}
+
 /// Scale all delays by a factor
 #[tauri::command]
 fn scale_delays(mut events: Vec<ScriptEvent>, factor: f64) -> Vec<ScriptEvent> {
     for event in &mut events {
         if let ScriptEvent::Delay { duration_ms } = event {
-            *duration_ms = (*duration_ms as f64 * factor) as u64;
+            *duration_ms = analysis::scaled_delay(*duration_ms, factor);
+        }
+    }
+    events
+}
+
+/// Scale only the delays at indices `start_idx..=end_idx`, e.g. to slow one app-load wait
+/// Indices past the end are clamped; an empty range leaves the events unchanged
+#[tauri::command]
+fn stretch_range(
+    mut events: Vec<ScriptEvent>,
+    start_idx: usize,
+    end_idx: usize,
+    factor: f64,
+) -> Vec<ScriptEvent> {
+    let end = end_idx.min(events.len().saturating_sub(1));
+    if start_idx > end || events.is_empty() {
+        return events;
+    }
+    for event in &mut events[start_idx..=end] {
+        if let ScriptEvent::Delay { duration_ms } = event {
+            *duration_ms = analysis::scaled_delay(*duration_ms, factor);
+        }
+    }
+    events
+}
+
+/// Before/after total duration and delay range of `scale_delays`, without the event list
+#[tauri::command]
+fn preview_scale_delays(events: Vec<ScriptEvent>, factor: f64) -> analysis::DurationPreview {
+    analysis::preview_scale_delays(&events, factor)
+}
+
+/// Swap every use of one key for another
+#[tauri::command]
+fn replace_key(
+    mut events: Vec<ScriptEvent>,
+    from: KeyboardKey,
+    to: KeyboardKey,
+) -> Vec<ScriptEvent> {
+    for event in &mut events {
+        match event {
+            ScriptEvent::KeyPress { key, key_code } | ScriptEvent::KeyRelease { key, key_code }
+                if *key == from =>
+            {
+                *key = to.clone();
+                // The recorded physical key belongs to the old key
+                *key_code = None;
+            }
+            ScriptEvent::KeyRepeat { key, .. } if *key == from => *key = to.clone(),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Swap every use of one mouse button for another
+#[tauri::command]
+fn replace_button(
+    mut events: Vec<ScriptEvent>,
+    from: MouseButton,
+    to: MouseButton,
+) -> Vec<ScriptEvent> {
+    for event in &mut events {
+        if let ScriptEvent::MousePress { button, .. } | ScriptEvent::MouseRelease { button, .. } =
+            event
+        {
+            if *button == from {
+                *button = to;
+            }
         }
     }
     events
 }
 
+/// Fix held-modifier regions after editing: drop orphan releases, release anything left held
+#[tauri::command]
+fn ensure_modifier_brackets(events: Vec<ScriptEvent>) -> Vec<ScriptEvent> {
+    chord::ensure_modifier_brackets(events)
+}
+
+/// `ensure_modifier_brackets` on a whole script, keeping the surviving events' ids
+#[tauri::command]
+fn ensure_script_modifier_brackets(mut script: Script) -> Script {
+    script.edit_events(chord::ensure_modifier_brackets);
+    script
+}
+
+/// Shift every mouse coordinate by a constant offset, e.g. after a window moved
+#[tauri::command]
+fn offset_coordinates(events: Vec<ScriptEvent>, dx: f64, dy: f64) -> Vec<ScriptEvent> {
+    transform::apply_transform(events, &transform::Transform::offset(dx, dy))
+}
+
+/// Compute the scale+offset transform mapping two recorded points onto where they are now
+#[tauri::command]
+fn calibrate(
+    record_point_a: transform::Point,
+    record_point_b: transform::Point,
+    current_point_a: transform::Point,
+    current_point_b: transform::Point,
+) -> Result<transform::Transform, String> {
+    transform::Transform::calibrate(
+        record_point_a,
+        record_point_b,
+        current_point_a,
+        current_point_b,
+    )
+}
+
+/// Remap every mouse coordinate through a calibration transform
+#[tauri::command]
+fn apply_transform(events: Vec<ScriptEvent>, transform: transform::Transform) -> Vec<ScriptEvent> {
+    transform::apply_transform(events, &transform)
+}
+
+/// Collapse modifier+key sequences (e.g. Ctrl+C) into single Chord events
+#[tauri::command]
+fn collapse_chords(events: Vec<ScriptEvent>) -> Vec<ScriptEvent> {
+    chord::collapse_chords(events)
+}
+
+/// `collapse_chords` on a whole script, keeping the surviving events' ids
+#[tauri::command]
+fn collapse_script_chords(mut script: Script) -> Script {
+    script.edit_events(chord::collapse_chords);
+    script
+}
+
+/// Thin out recorded mouse paths, dropping moves within `epsilon` pixels of the line
+#[tauri::command]
+fn simplify_path(events: Vec<ScriptEvent>, epsilon: f64) -> Vec<ScriptEvent> {
+    simplify::simplify_path(events, epsilon)
+}
+
+/// `simplify_path` on a whole script, keeping the surviving events' ids
+#[tauri::command]
+fn simplify_script_path(mut script: Script, epsilon: f64) -> Script {
+    script.edit_events(|events| simplify::simplify_path(events, epsilon));
+    script
+}
+
+/// Raise every delay shorter than `min_ms` up to it
+#[tauri::command]
+fn floor_delays(mut events: Vec<ScriptEvent>, min_ms: u64) -> Vec<ScriptEvent> {
+    for event in &mut events {
+        if let ScriptEvent::Delay { duration_ms } = event {
+            *duration_ms = (*duration_ms).max(min_ms);
+        }
+    }
+    events
+}
+
+/// Insert a KeyRepeat event at index (appends if index is past the end)
+#[tauri::command]
+fn insert_key_repeat(
+    mut events: Vec<ScriptEvent>,
+    index: usize,
+    key: KeyboardKey,
+    count: u32,
+    interval_ms: u64,
+) -> Vec<ScriptEvent> {
+    let index = index.min(events.len());
+    events.insert(
+        index,
+        ScriptEvent::KeyRepeat {
+            key,
+            count,
+            interval_ms,
+        },
+    );
+    events
+}
+
+/// Compare two scripts, returning insertions, deletions and delay changes
+#[tauri::command]
+fn diff_scripts(a: Script, b: Script) -> Result<Vec<diff::ScriptDiff>, String> {
+    diff::diff_events(&a.events, &b.events)
+}
+
+/// Re-time a fresh recording with the delays of a polished reference recording
+#[tauri::command]
+fn align_timing(source: Vec<ScriptEvent>, reference: Vec<ScriptEvent>) -> Vec<ScriptEvent> {
+    diff::align_timing(&source, &reference)
+}
+
+/// `align_timing` on a whole script, keeping the surviving events' ids
+#[tauri::command]
+fn align_script_timing(mut source: Script, reference: Vec<ScriptEvent>) -> Script {
+    source.edit_events(|events| diff::align_timing(&events, &reference));
+    source
+}
+
+/// Estimate how long a script will take to play, for display before starting
+/// Returns `u64::MAX` for infinite loops
+#[tauri::command]
+fn estimated_playback_ms(script: Script) -> u64 {
+    analysis::estimated_playback_ms(&script)
+}
+
+/// Summarize a script's first `max` actions, for tooltips in the script picker
+#[tauri::command]
+fn describe_script(script: Script, max: usize) -> String {
+    analysis::describe_script(&script, max)
+}
+
+/// Render events as a compact one-line string for previews and logs
+#[tauri::command]
+fn events_to_string(events: Vec<ScriptEvent>) -> String {
+    analysis::events_to_string(&events)
+}
+
+/// Translate a script into AutoHotkey v2 source
+#[tauri::command]
+fn export_ahk(script: Script) -> String {
+    ahk::export_ahk(&script)
+}
+
+/// Event counts by type, duration and file size of a script
+#[tauri::command]
+fn script_metrics(script: Script) -> Result<analysis::ScriptMetrics, String> {
+    analysis::script_metrics(&script)
+}
+
+/// Count events per time bucket for the editor's activity histogram
+#[tauri::command]
+fn timeline_summary(events: Vec<ScriptEvent>, buckets: usize) -> Vec<u32> {
+    analysis::timeline_summary(&events, buckets)
+}
+
+// ============================================================================
+// Settings Commands
+// ============================================================================
+
+/// Get the current application settings
+#[tauri::command]
+fn get_settings() -> AppSettings {
+    settings::get_settings()
+}
+
+/// Update the overlay style
+#[tauri::command]
+fn set_overlay_style(style: OverlayStyle) -> Result<(), String> {
+    settings::set_overlay_style(style)
+}
+
+/// Update the debounce window applied before task triggers
+#[tauri::command]
+fn set_trigger_debounce_ms(ms: u64) -> Result<(), String> {
+    settings::set_trigger_debounce_ms(ms)
+}
+
+/// Replace the keys task triggers still see while a script plays
+#[tauri::command]
+fn set_playback_passthrough_keys(keys: Vec<KeyboardKey>) -> Result<(), String> {
+    settings::set_playback_passthrough_keys(keys)
+}
+
+/// Turn the automation master switch on or off; off also stops playback and recording
+#[tauri::command]
+fn set_automation_enabled(enabled: bool) -> Result<(), String> {
+    input_manager::set_automation_enabled(enabled)
+}
+
 // ============================================================================
 // App State Commands
 // ============================================================================
 
+#[derive(Clone, serde::Serialize)]
+struct PlaybackProgress {
+    playing: bool,
+    stepping: bool,
+    elapsed_ms: u64,
+    event_index: usize,
+    current_loop: u32,
+}
+
 #[derive(Clone, serde::Serialize)]
 struct AppState {
     recording: bool,
     playing: bool,
+    stepping: bool,
     task_listener_active: bool,
+    automation_enabled: bool,
+}
+
+/// Report which playback/recording features this platform supports
+#[tauri::command]
+fn get_capabilities() -> capabilities::Capabilities {
+    capabilities::detect()
 }
 
 #[tauri::command]
@@ -332,7 +1197,9 @@ fn get_app_state() -> AppState {
     AppState {
         recording: recorder::is_recording(),
         playing: player::is_playing(),
+        stepping: player::get_state().is_stepping(),
         task_listener_active: macro_trigger::get_state().is_active(),
+        automation_enabled: settings::get_settings().automation_enabled,
     }
 }
 
@@ -347,6 +1214,7 @@ use tauri::{
         TrayIconEvent,
     },
 };
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -354,9 +1222,36 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .targets([
+                    Target::new(TargetKind::Stdout),
+                    Target::new(TargetKind::LogDir {
+                        file_name: Some(LOG_FILE_NAME.to_string()),
+                    }),
+                ])
+                .level(log::LevelFilter::Info)
+                .max_file_size(1_000_000)
+                .rotation_strategy(RotationStrategy::KeepOne)
+                .build(),
+        )
         .setup(|app| {
             input_manager::init(app.handle().clone());
 
+            if let Ok(dir) = data_dir(app.handle()) {
+                let _ = fs::create_dir_all(&dir);
+                match macro_trigger::init_storage(dir.join(TASKS_FILE)) {
+                    Ok(()) => macro_trigger::warn_invalid_tasks(),
+                    Err(e) => log::error!("Failed to load tasks: {}", e),
+                }
+                if let Err(e) = settings::init_storage(dir.join(CONFIG_FILE)) {
+                    log::error!("Failed to load settings: {}", e);
+                }
+                if let Err(e) = scheduler::init(dir.join(SCHEDULES_FILE)) {
+                    log::error!("Failed to load schedules: {}", e);
+                }
+            }
+
             let _ = WebviewWindowBuilder::new(
                 app,
                 "overlay",
@@ -416,31 +1311,112 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             release_main_window,
             release_overlay_window,
+            force_hide_overlay,
+            set_overlay_clickthrough,
+            set_overlay_topmost,
             start_recording,
             stop_recording,
+            start_recording_timed,
+            start_recording_until,
             is_recording,
             get_recorded_events,
+            get_last_recording,
+            get_recording_scale_factor,
+            clear_recording,
             record_frontend_event,
+            record_frontend_composition,
+            start_frontend_recording,
+            stop_frontend_recording,
             play_script,
+            play_script_blocking,
+            play_saved_script,
             play_events,
+            execute_single_event,
+            visualize_script,
             stop_playback,
+            play_script_stepping,
+            step_playback,
+            get_playback_progress,
+            get_remaining_loops,
+            set_remaining_loops,
             is_playing,
+            cancel_all,
+            list_special_keys,
+            type_text,
+            schedule_playback,
+            list_scheduled,
+            schedule_recurring,
+            list_recurring,
+            cancel_scheduled,
             save_script,
             load_script,
+            load_script_lenient,
+            load_events,
+            save_events,
             get_scripts_dir,
+            get_data_paths,
+            get_log_path,
             delete_script,
             add_task,
+            check_trigger_conflict,
             remove_task,
             get_all_tasks,
             toggle_task,
+            get_task_stats,
+            macro_trigger_map,
+            validate_macro,
+            set_task_events,
             start_task_listener,
             stop_task_listener,
             create_task_binding,
+            parse_trigger,
+            save_as_task,
             list_saved_scripts,
+            export_bundle,
+            import_bundle,
+            import_scripts_from_dir,
             update_event_delay,
             delete_event,
+            delete_script_event,
+            insert_script_event,
+            move_script_event,
+            assign_event_ids,
+            toggle_event,
+            split_script,
             scale_delays,
+            preview_scale_delays,
+            stretch_range,
+            floor_delays,
+            insert_key_repeat,
+            timeline_summary,
+            script_metrics,
+            export_ahk,
+            events_to_string,
+            simplify_path,
+            simplify_script_path,
+            collapse_chords,
+            collapse_script_chords,
+            offset_coordinates,
+            calibrate,
+            apply_transform,
+            ensure_modifier_brackets,
+            ensure_script_modifier_brackets,
+            get_cursor_position,
+            get_work_area,
+            replace_key,
+            replace_button,
+            estimated_playback_ms,
+            describe_script,
+            diff_scripts,
+            align_timing,
+            align_script_timing,
+            get_settings,
+            set_overlay_style,
+            set_trigger_debounce_ms,
+            set_playback_passthrough_keys,
+            set_automation_enabled,
             get_app_state,
+            get_capabilities,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -2,11 +2,12 @@
 //! Listener moved to input_manager
 
 use crate::player;
-use crate::script::{KeyboardKey, Script, Task};
+use crate::script::{KeyboardKey, Script, ScriptEvent, Task};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::fs;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -20,6 +21,51 @@ pub struct TaskState {
     is_active: AtomicBool,
     /// Registered tasks (key: ID, value: task definition)
     tasks: RwLock<HashMap<String, Task>>,
+    /// File the task list is persisted to (None until storage is initialized)
+    storage_path: RwLock<Option<PathBuf>>,
+    /// Times each task has fired since startup (key: ID)
+    fire_counts: RwLock<HashMap<String, u64>>,
+}
+
+/// How often a task has fired since startup
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TaskStat {
+    pub id: String,
+    pub name: String,
+    pub fired: u64,
+}
+
+/// Whether a task has something playable, as reported by `validate_macro`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MacroValidation {
+    pub id: String,
+    pub name: String,
+    /// Number of events the task would play, if they could be loaded
+    pub event_count: Option<usize>,
+    /// Why the task can't play (missing file, parse error, nothing to play)
+    pub error: Option<String>,
+}
+
+/// Check that a task's inline events or script file can be played
+fn validate_task(task: &Task) -> MacroValidation {
+    let events = match &task.events {
+        Some(events) if !events.is_empty() => Ok(events.len()),
+        _ if task.script_path.is_empty() => Err("No script or events to play".to_string()),
+        _ if !std::path::Path::new(&task.script_path).exists() => {
+            Err(format!("Script not found: {}", task.script_path))
+        }
+        _ => Script::load(&task.script_path).map(|script| script.events.len()),
+    };
+    let (event_count, error) = match events {
+        Ok(count) => (Some(count), None),
+        Err(e) => (None, Some(e)),
+    };
+    MacroValidation {
+        id: task.id.clone(),
+        name: task.name.clone(),
+        event_count,
+        error,
+    }
 }
 
 impl TaskState {
@@ -27,9 +73,40 @@ impl TaskState {
         Self {
             is_active: AtomicBool::new(false),
             tasks: RwLock::new(HashMap::new()),
+            storage_path: RwLock::new(None),
+            fire_counts: RwLock::new(HashMap::new()),
         }
     }
 
+    /// Load tasks from `path` and persist future changes there
+    pub fn init_storage(&self, path: PathBuf) -> Result<(), String> {
+        if path.exists() {
+            let content =
+                fs::read_to_string(&path).map_err(|e| format!("File read error: {}", e))?;
+            let tasks: Vec<Task> =
+                serde_json::from_str(&content).map_err(|e| format!("Parse error: {}", e))?;
+            // Replace rather than merge, so reloading after an import drops the old tasks
+            let mut map = self.tasks.write();
+            map.clear();
+            for task in tasks {
+                map.insert(task.id.clone(), task);
+            }
+        }
+        *self.storage_path.write() = Some(path);
+        Ok(())
+    }
+
+    /// Write the task list to the storage file, if one is configured
+    pub fn persist(&self) -> Result<(), String> {
+        let path = match self.storage_path.read().clone() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let json = serde_json::to_string_pretty(&self.get_all_tasks())
+            .map_err(|e| format!("Serialization error: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("File write error: {}", e))
+    }
+
     pub fn is_active(&self) -> bool {
         self.is_active.load(Ordering::SeqCst)
     }
@@ -62,6 +139,16 @@ impl TaskState {
             .cloned()
     }
 
+    /// Find another task (different ID) bound to the same trigger key
+    pub fn find_trigger_conflict(&self, task: &Task) -> Option<Task> {
+        let key = task.trigger_key.as_ref()?;
+        self.tasks
+            .read()
+            .values()
+            .find(|t| t.id != task.id && t.trigger_key.as_ref() == Some(key))
+            .cloned()
+    }
+
     /// Find task by stop key
     pub fn find_by_stop(&self, key: &KeyboardKey) -> Option<Task> {
         self.tasks
@@ -71,6 +158,75 @@ impl TaskState {
             .cloned()
     }
 
+    /// Fire counts for every registered task, most used first
+    pub fn stats(&self) -> Vec<TaskStat> {
+        let counts = self.fire_counts.read();
+        let mut stats: Vec<TaskStat> = self
+            .tasks
+            .read()
+            .values()
+            .map(|task| TaskStat {
+                id: task.id.clone(),
+                name: task.name.clone(),
+                fired: counts.get(&task.id).copied().unwrap_or(0),
+            })
+            .collect();
+        stats.sort_by(|a, b| b.fired.cmp(&a.fired).then_with(|| a.name.cmp(&b.name)));
+        stats
+    }
+
+    /// (trigger description, task name) for every task, sorted so shared triggers sit together
+    pub fn trigger_map(&self) -> Vec<(String, String)> {
+        let mut map: Vec<(String, String)> = self
+            .tasks
+            .read()
+            .values()
+            .map(|task| {
+                let mut trigger = match &task.trigger_key {
+                    Some(key) => key.display_name(),
+                    None => "No trigger".to_string(),
+                };
+                if let Some(stop) = &task.stop_key {
+                    trigger.push_str(&format!(" (stop: {})", stop.display_name()));
+                }
+                if !task.enabled {
+                    trigger.push_str(" [disabled]");
+                }
+                (trigger, task.name.clone())
+            })
+            .collect();
+        map.sort();
+        map
+    }
+
+    /// Check one task's script
+    pub fn validate(&self, id: &str) -> Result<MacroValidation, String> {
+        let tasks = self.tasks.read();
+        let task = tasks
+            .get(id)
+            .ok_or_else(|| format!("Task not found: {}", id))?;
+        Ok(validate_task(task))
+    }
+
+    /// Check the scripts of every enabled task
+    pub fn validate_enabled(&self) -> Vec<MacroValidation> {
+        self.tasks
+            .read()
+            .values()
+            .filter(|task| task.enabled)
+            .map(validate_task)
+            .collect()
+    }
+
+    /// Stop playback if the key is a task's stop key
+    pub fn check_stop_key(&self, key: &KeyboardKey) -> bool {
+        if !self.is_active() || !player::is_playing() || self.find_by_stop(key).is_none() {
+            return false;
+        }
+        player::stop_playback();
+        true
+    }
+
     /// Check if a key press should trigger or stop a task
     pub fn check_key_event(&self, key: &KeyboardKey) -> bool {
         if !self.is_active() {
@@ -78,41 +234,44 @@ impl TaskState {
         }
 
         // 1. Check if it's a stop key for a running task
-        if player::is_playing() {
-            if let Some(_task) = self.find_by_stop(key) {
-                player::stop_playback();
-                return true;
-            }
+        if self.check_stop_key(key) {
+            return true;
         }
 
         // 2. Check if it's a trigger key for a task
+        if !crate::settings::get_settings().automation_enabled {
+            return false;
+        }
         if let Some(task) = self.find_by_trigger(key) {
-            if task.enabled && !task.script_path.is_empty() {
+            let has_inline_events = task.events.as_ref().is_some_and(|e| !e.is_empty());
+            let (num_lock, caps_lock) = crate::input_manager::lock_state();
+            let locks_ok = task
+                .requires_lock_state
+                .is_none_or(|required| required.matches(num_lock, caps_lock));
+            if task.enabled && locks_ok && (has_inline_events || !task.script_path.is_empty()) {
                 // If already playing, stop first?
                 // Or only play if not playing?
                 if player::is_playing() {
                     player::stop_playback();
                     // Optional: delay or wait for stop
                 }
-
-                let path = task.script_path.clone();
-                let loop_config = task.loop_config.clone();
-                let speed_multiplier = task.speed_multiplier;
+                *self.fire_counts.write().entry(task.id.clone()).or_insert(0) += 1;
 
                 // Spawn thread to execute task script
                 thread::spawn(move || {
-                    if let Ok(content) = fs::read_to_string(&path) {
-                        match serde_json::from_str::<Script>(&content) {
-                            Ok(mut script) => {
-                                // Override script settings with task settings
-                                script.loop_config = loop_config;
-                                script.speed_multiplier = speed_multiplier;
-                                let _ = player::play_script(script);
-                            }
-                            Err(e) => eprintln!("Failed to parse script {}: {}", path, e),
-                        }
-                    } else {
-                        eprintln!("Failed to read script: {}", path);
+                    let script = match task.events {
+                        Some(events) if !events.is_empty() => Some(Script {
+                            events,
+                            ..Default::default()
+                        }),
+                        _ => read_task_script(&task.script_path),
+                    };
+
+                    if let Some(mut script) = script {
+                        // Override script settings with task settings
+                        script.loop_config = task.loop_config;
+                        script.speed_multiplier = task.speed_multiplier;
+                        let _ = player::play_script(script);
                     }
                 });
                 return true;
@@ -122,6 +281,23 @@ impl TaskState {
     }
 }
 
+/// Read and parse the script file a task points at
+fn read_task_script(path: &str) -> Option<Script> {
+    match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str::<Script>(&content) {
+            Ok(script) => Some(script),
+            Err(e) => {
+                log::error!("Failed to parse script {}: {}", path, e);
+                None
+            }
+        },
+        Err(_) => {
+            log::error!("Failed to read script: {}", path);
+            None
+        }
+    }
+}
+
 impl Default for TaskState {
     fn default() -> Self {
         Self::new()
@@ -133,6 +309,18 @@ pub fn get_state() -> Arc<TaskState> {
     Arc::clone(&TASK_STATE)
 }
 
+/// Persist the task list, logging failures
+fn persist_tasks() {
+    if let Err(e) = get_state().persist() {
+        log::error!("Failed to persist tasks: {}", e);
+    }
+}
+
+/// Load persisted tasks and enable persistence
+pub fn init_storage(path: PathBuf) -> Result<(), String> {
+    get_state().init_storage(path)
+}
+
 /// Start task listening
 pub fn start_task_listener() -> Result<(), String> {
     let state = get_state();
@@ -148,11 +336,35 @@ pub fn stop_task_listener() {
 /// Add a new task
 pub fn add_task(task: Task) {
     get_state().add_task(task);
+    persist_tasks();
+}
+
+/// Add a task, refusing to shadow another task's trigger unless `replace_existing` is set
+pub fn add_task_checked(task: Task, replace_existing: bool) -> Result<(), String> {
+    if !replace_existing {
+        ensure_trigger_free(&task)?;
+    }
+    add_task(task);
+    Ok(())
+}
+
+/// Error if another task is already bound to this task's trigger
+pub fn ensure_trigger_free(task: &Task) -> Result<(), String> {
+    match get_state().find_trigger_conflict(task) {
+        Some(existing) => Err(format!("Trigger already bound to task '{}'", existing.name)),
+        None => Ok(()),
+    }
+}
+
+/// Find the task currently bound to a trigger key
+pub fn check_trigger_conflict(trigger_key: &KeyboardKey) -> Option<Task> {
+    get_state().find_by_trigger(trigger_key)
 }
 
 /// Remove a task by ID
 pub fn remove_task(id: &str) {
     get_state().remove_task(id);
+    persist_tasks();
 }
 
 /// Get all registered tasks
@@ -160,13 +372,68 @@ pub fn get_all_tasks() -> Vec<Task> {
     get_state().get_all_tasks()
 }
 
+/// Fire counts for every registered task since startup
+pub fn get_task_stats() -> Vec<TaskStat> {
+    get_state().stats()
+}
+
+/// Every task's trigger and name, for auditing key bindings
+pub fn trigger_map() -> Vec<(String, String)> {
+    get_state().trigger_map()
+}
+
+/// Check that a task's script exists and parses
+pub fn validate_macro(id: &str) -> Result<MacroValidation, String> {
+    get_state().validate(id)
+}
+
+/// Log and emit `macro-invalid` for every enabled task that couldn't play
+/// Run at startup, so a dead task shows up before its key is pressed
+pub fn warn_invalid_tasks() {
+    for validation in get_state().validate_enabled() {
+        if let Some(error) = &validation.error {
+            log::warn!("Task \"{}\" can't play: {}", validation.name, error);
+            crate::input_manager::emit_event("macro-invalid", validation.clone());
+        }
+    }
+}
+
 /// Toggle task enabled state
 pub fn toggle_task(id: &str, enabled: bool) {
     let state = get_state();
-    let mut tasks = state.tasks.write();
-    if let Some(task) = tasks.get_mut(id) {
+    if let Some(task) = state.tasks.write().get_mut(id) {
         task.enabled = enabled;
     }
+    persist_tasks();
+}
+
+/// Replace a task's inline events
+pub fn set_task_events(id: &str, events: Vec<ScriptEvent>) -> Result<Task, String> {
+    let state = get_state();
+    let task = {
+        let mut tasks = state.tasks.write();
+        let task = tasks
+            .get_mut(id)
+            .ok_or_else(|| format!("Task not found: {}", id))?;
+        task.events = Some(events);
+        task.clone()
+    };
+    state.persist()?;
+    Ok(task)
+}
+
+/// Events a task plays: its inline events, or those of its script file
+pub fn task_events(id: &str) -> Result<Vec<ScriptEvent>, String> {
+    let task = get_state()
+        .tasks
+        .read()
+        .get(id)
+        .cloned()
+        .ok_or_else(|| format!("Task not found: {}", id))?;
+    match task.events {
+        Some(events) if !events.is_empty() => Ok(events),
+        _ => Script::load(&task.script_path).map(|script| script.events),
+    }
 }
 
 pub fn uuid_simple() -> String {
@@ -0,0 +1,26 @@
+//! Pixel module - samples screen colors for conditional events
+//! Uses screenshots for cross-platform screen capture
+
+/// Read the RGB color of the screen pixel at absolute coordinates
+pub fn get_pixel(x: i32, y: i32) -> Result<[u8; 3], String> {
+    let screen =
+        screenshots::Screen::from_point(x, y).map_err(|e| format!("Screen lookup error: {}", e))?;
+    let origin_x = screen.display_info.x;
+    let origin_y = screen.display_info.y;
+
+    // capture_area takes coordinates relative to the screen's top-left corner
+    let image = screen
+        .capture_area(x - origin_x, y - origin_y, 1, 1)
+        .map_err(|e| format!("Screen capture error: {}", e))?;
+    let pixel = image.get_pixel(0, 0);
+
+    Ok([pixel[0], pixel[1], pixel[2]])
+}
+
+/// Check whether two colors match within a per-channel tolerance
+pub fn color_matches(actual: [u8; 3], expected: [u8; 3], tolerance: u8) -> bool {
+    actual
+        .iter()
+        .zip(expected.iter())
+        .all(|(a, e)| a.abs_diff(*e) <= tolerance)
+}
@@ -1,28 +1,78 @@
 //! Playback module - simulates keyboard and mouse events
 //! Uses enigo for input simulation
 
-use crate::script::{KeyboardKey, Script, ScriptEvent};
+use crate::cancel::CancellationToken;
+use crate::pixel;
+use crate::script::{KeyboardKey, LoopConfig, MouseButton, Rect, Script, ScriptEvent, EXTRA_KEYS};
 use enigo::{Enigo, Keyboard, Mouse, Settings};
 use once_cell::sync::Lazy;
-use parking_lot::Mutex;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use parking_lot::{Condvar, Mutex};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Maximum number of jumps per second before playback is throttled
+/// Guards against tight jump loops spinning the CPU
+const MAX_JUMPS_PER_SECOND: u32 = 1000;
+
+/// Fastest speed multiplier playback accepts; higher values are clamped
+const MAX_SPEED_MULTIPLIER: f64 = 100.0;
+
+/// How often WaitForPixelOptional re-reads the pixel
+const PIXEL_POLL_MS: u64 = 50;
+
+/// How deeply `RunTask` events may nest
+const MAX_TASK_DEPTH: usize = 8;
+
+/// How often the target-window watchdog checks the foreground window
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Checks in a row the target window may be missing before the watchdog aborts
+/// Leaves the script time to bring it back after switching windows
+const WATCHDOG_MISSES: u32 = 3;
 
 /// Global playback state
 static PLAYBACK_STATE: Lazy<Arc<PlaybackState>> = Lazy::new(|| Arc::new(PlaybackState::new()));
 
+/// Channel to the playback worker thread
+static PLAYBACK_WORKER: Lazy<Mutex<mpsc::Sender<Script>>> =
+    Lazy::new(|| Mutex::new(spawn_worker()));
+
 /// Playback state manager
 pub struct PlaybackState {
     /// Whether playback is active
     is_playing: AtomicBool,
     /// Current loop iteration
     current_loop: Mutex<u32>,
+    /// Last loop iteration to run (None for infinite); may change mid-playback
+    target_loops: Mutex<Option<u32>>,
     /// Current event index
     current_event: Mutex<usize>,
-    /// Stop requested flag
-    stop_requested: AtomicBool,
+    /// When the current playback started
+    started_at: Mutex<Option<Instant>>,
+    /// Tripped by stop requests and `cancel_all`
+    stop_token: CancellationToken,
+    /// Scripts sent to the worker but not yet picked up
+    queued: AtomicUsize,
+    /// Scripts ever sent to the worker; a script's ticket is the count after sending it
+    submitted: AtomicU64,
+    /// Scripts the worker is done with, in send order
+    completed: Mutex<u64>,
+    /// Signalled whenever the worker completes a script
+    completed_signal: Condvar,
+    /// Values captured by CaptureClipboard events during the current run
+    vars: Mutex<HashMap<String, String>>,
+    /// Step mode: the worker waits for a `step_playback` before each event
+    stepping: AtomicBool,
+    /// Steps requested but not yet taken
+    step_permits: Mutex<u32>,
+    /// Signalled when a step is requested or step mode ends
+    step_signal: Condvar,
+    /// Title of the window the last WindowAnchor pointed at, watched by the watchdog
+    target_window: Mutex<Option<String>>,
 }
 
 impl PlaybackState {
@@ -30,8 +80,19 @@ impl PlaybackState {
         Self {
             is_playing: AtomicBool::new(false),
             current_loop: Mutex::new(0),
+            target_loops: Mutex::new(None),
             current_event: Mutex::new(0),
-            stop_requested: AtomicBool::new(false),
+            started_at: Mutex::new(None),
+            stop_token: CancellationToken::registered(),
+            queued: AtomicUsize::new(0),
+            submitted: AtomicU64::new(0),
+            completed: Mutex::new(0),
+            completed_signal: Condvar::new(),
+            vars: Mutex::new(HashMap::new()),
+            stepping: AtomicBool::new(false),
+            step_permits: Mutex::new(0),
+            step_signal: Condvar::new(),
+            target_window: Mutex::new(None),
         }
     }
 
@@ -39,20 +100,70 @@ impl PlaybackState {
         self.is_playing.load(Ordering::SeqCst)
     }
 
-    pub fn start(&self) {
+    /// Set the playing flag, returning false if another playback already holds it
+    fn claim(&self) -> bool {
+        self.is_playing
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// Reset progress and the stop token for a run that has claimed the playing flag
+    fn start(&self) {
         *self.current_loop.lock() = 0;
         *self.current_event.lock() = 0;
-        self.stop_requested.store(false, Ordering::SeqCst);
-        self.is_playing.store(true, Ordering::SeqCst);
+        *self.started_at.lock() = Some(Instant::now());
+        self.vars.lock().clear();
+        *self.step_permits.lock() = 0;
+        *self.target_window.lock() = None;
+        self.stop_token.reset();
     }
 
     pub fn stop(&self) {
-        self.stop_requested.store(true, Ordering::SeqCst);
+        self.stop_token.cancel();
         self.is_playing.store(false, Ordering::SeqCst);
+        self.set_stepping(false);
+    }
+
+    pub fn is_stepping(&self) -> bool {
+        self.stepping.load(Ordering::SeqCst)
+    }
+
+    /// Enter or leave step mode; leaving it lets a waiting worker run on
+    pub fn set_stepping(&self, enabled: bool) {
+        self.stepping.store(enabled, Ordering::SeqCst);
+        if !enabled {
+            self.step_signal.notify_all();
+        }
+    }
+
+    /// Let the worker run one more event while in step mode
+    pub fn step(&self) {
+        *self.step_permits.lock() += 1;
+        self.step_signal.notify_all();
     }
 
+    /// Block until a step is requested, returning false if playback stopped meanwhile
+    /// Returns immediately outside step mode
+    fn wait_for_step(&self) -> bool {
+        let mut permits = self.step_permits.lock();
+        while self.is_stepping() && *permits == 0 {
+            if self.should_stop() {
+                return false;
+            }
+            // Stop requests don't signal the condvar, so wake up to poll for them
+            self.step_signal
+                .wait_for(&mut permits, Duration::from_millis(100));
+        }
+        if self.is_stepping() {
+            *permits -= 1;
+        }
+        !self.should_stop()
+    }
+
+    /// Whether the running script should stop
+    /// Also true when a newer script is waiting for the worker
     pub fn should_stop(&self) -> bool {
-        self.stop_requested.load(Ordering::SeqCst)
+        self.stop_token.is_cancelled() || self.queued.load(Ordering::SeqCst) > 0
     }
 
     pub fn increment_loop(&self) -> u32 {
@@ -61,12 +172,86 @@ impl PlaybackState {
         *loop_count
     }
 
+    /// Run `count` loop iterations in total (0 for infinite)
+    pub fn set_loop_count(&self, count: u32) {
+        *self.target_loops.lock() = (count > 0).then_some(count);
+    }
+
+    /// Last loop iteration to run (None for infinite)
+    pub fn target_loops(&self) -> Option<u32> {
+        *self.target_loops.lock()
+    }
+
+    /// Iterations left after the current one (None for infinite)
+    pub fn remaining_loops(&self) -> Option<u32> {
+        let current = *self.current_loop.lock();
+        self.target_loops
+            .lock()
+            .map(|target| target.saturating_sub(current))
+    }
+
+    /// Run `count` more iterations after the current one, turning an infinite run finite
+    pub fn set_remaining_loops(&self, count: u32) {
+        let current = *self.current_loop.lock();
+        *self.target_loops.lock() = Some(current.saturating_add(count));
+    }
+
+    /// Count a script as done on the worker and wake anyone waiting for it
+    fn mark_completed(&self) {
+        *self.completed.lock() += 1;
+        self.completed_signal.notify_all();
+    }
+
+    /// Block until the worker is done with the script holding `ticket`
+    fn wait_for_ticket(&self, ticket: u64) {
+        let mut completed = self.completed.lock();
+        while *completed < ticket {
+            self.completed_signal.wait(&mut completed);
+        }
+    }
+
+    fn set_target_window(&self, title: &str) {
+        *self.target_window.lock() = Some(title.to_string());
+    }
+
+    fn target_window(&self) -> Option<String> {
+        self.target_window.lock().clone()
+    }
+
     pub fn set_event_index(&self, index: usize) {
         *self.current_event.lock() = index;
     }
 
+    pub fn set_var(&self, name: &str, value: String) {
+        self.vars.lock().insert(name.to_string(), value);
+    }
+
+    pub fn get_var(&self, name: &str) -> Option<String> {
+        self.vars.lock().get(name).cloned()
+    }
+
+    /// Elapsed ms since start, current event index and current loop
+    pub fn progress(&self) -> (u64, usize, u32) {
+        let elapsed = self
+            .started_at
+            .lock()
+            .map(|t| t.elapsed().as_millis() as u64)
+            .unwrap_or(0);
+        (
+            elapsed,
+            *self.current_event.lock(),
+            *self.current_loop.lock(),
+        )
+    }
+
     pub fn finish(&self) {
+        // A newer script is queued; keep the playing state for it
+        if self.queued.load(Ordering::SeqCst) > 0 {
+            return;
+        }
+
         self.is_playing.store(false, Ordering::SeqCst);
+        self.set_stepping(false);
 
         // Cleanup UI via input_manager
         crate::input_manager::on_playback_finish();
@@ -94,121 +279,449 @@ pub fn get_state() -> Arc<PlaybackState> {
     Arc::clone(&PLAYBACK_STATE)
 }
 
+/// Special key names playback understands, with the enigo key each maps to
+/// Keys in `EXTRA_KEYS` are supported too
+const SPECIAL_KEYS: &[(&str, enigo::Key)] = &[
+    ("Alt", enigo::Key::Alt),
+    ("Backspace", enigo::Key::Backspace),
+    ("CapsLock", enigo::Key::CapsLock),
+    ("ControlLeft", enigo::Key::Control),
+    ("ControlRight", enigo::Key::Control),
+    ("Delete", enigo::Key::Delete),
+    ("DownArrow", enigo::Key::DownArrow),
+    ("End", enigo::Key::End),
+    ("Escape", enigo::Key::Escape),
+    ("F1", enigo::Key::F1),
+    ("F2", enigo::Key::F2),
+    ("F3", enigo::Key::F3),
+    ("F4", enigo::Key::F4),
+    ("F5", enigo::Key::F5),
+    ("F6", enigo::Key::F6),
+    ("F7", enigo::Key::F7),
+    ("F8", enigo::Key::F8),
+    ("F9", enigo::Key::F9),
+    ("F10", enigo::Key::F10),
+    ("F11", enigo::Key::F11),
+    ("F12", enigo::Key::F12),
+    ("Home", enigo::Key::Home),
+    ("LeftArrow", enigo::Key::LeftArrow),
+    ("MetaLeft", enigo::Key::Meta),
+    ("MetaRight", enigo::Key::Meta),
+    ("Super", enigo::Key::Meta),
+    ("PageDown", enigo::Key::PageDown),
+    ("PageUp", enigo::Key::PageUp),
+    ("Return", enigo::Key::Return),
+    ("RightArrow", enigo::Key::RightArrow),
+    ("ShiftLeft", enigo::Key::Shift),
+    ("ShiftRight", enigo::Key::Shift),
+    ("Space", enigo::Key::Space),
+    ("Tab", enigo::Key::Tab),
+    ("UpArrow", enigo::Key::UpArrow),
+];
+
 /// Convert KeyboardKey to enigo Key
 fn keyboard_key_to_enigo(key: &KeyboardKey) -> Option<enigo::Key> {
     match key {
         KeyboardKey::Char(c) => Some(enigo::Key::Unicode(*c)),
-        KeyboardKey::Special(s) => match s.as_str() {
-            "Alt" => Some(enigo::Key::Alt),
-            "Backspace" => Some(enigo::Key::Backspace),
-            "CapsLock" => Some(enigo::Key::CapsLock),
-            "ControlLeft" | "ControlRight" => Some(enigo::Key::Control),
-            "Delete" => Some(enigo::Key::Delete),
-            "DownArrow" => Some(enigo::Key::DownArrow),
-            "End" => Some(enigo::Key::End),
-            "Escape" => Some(enigo::Key::Escape),
-            "F1" => Some(enigo::Key::F1),
-            "F2" => Some(enigo::Key::F2),
-            "F3" => Some(enigo::Key::F3),
-            "F4" => Some(enigo::Key::F4),
-            "F5" => Some(enigo::Key::F5),
-            "F6" => Some(enigo::Key::F6),
-            "F7" => Some(enigo::Key::F7),
-            "F8" => Some(enigo::Key::F8),
-            "F9" => Some(enigo::Key::F9),
-            "F10" => Some(enigo::Key::F10),
-            "F11" => Some(enigo::Key::F11),
-            "F12" => Some(enigo::Key::F12),
-            "Home" => Some(enigo::Key::Home),
-            "LeftArrow" => Some(enigo::Key::LeftArrow),
-            "MetaLeft" | "MetaRight" => Some(enigo::Key::Meta),
-            "PageDown" => Some(enigo::Key::PageDown),
-            "PageUp" => Some(enigo::Key::PageUp),
-            "Return" => Some(enigo::Key::Return),
-            "RightArrow" => Some(enigo::Key::RightArrow),
-            "ShiftLeft" | "ShiftRight" => Some(enigo::Key::Shift),
-            "Space" => Some(enigo::Key::Space),
-            "Tab" => Some(enigo::Key::Tab),
-            "UpArrow" => Some(enigo::Key::UpArrow),
+        KeyboardKey::Special(s) => SPECIAL_KEYS
+            .iter()
+            .find(|(name, _)| name == s)
+            .map(|(_, key)| *key)
+            .or_else(|| {
+                EXTRA_KEYS
+                    .iter()
+                    .find(|(name, _, _)| name == s)
+                    .map(|(_, _, code)| enigo::Key::Other(*code))
+            }),
+    }
+}
+
+/// Every special key name `keyboard_key_to_enigo` accepts on this platform
+pub fn special_key_names() -> Vec<String> {
+    SPECIAL_KEYS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(EXTRA_KEYS.iter().map(|(name, _, _)| name.to_string()))
+        .collect()
+}
+
+/// Ensure every jump target points inside the event list (or just past its end)
+fn validate_jumps(events: &[ScriptEvent]) -> Result<(), String> {
+    for (index, event) in events.iter().enumerate() {
+        let in_range = match event {
+            ScriptEvent::IfPixel {
+                then_jump,
+                else_jump,
+                ..
+            } => *then_jump <= events.len() && *else_jump <= events.len(),
+            ScriptEvent::Jump { target } => *target <= events.len(),
+            _ => true,
+        };
+        if !in_range {
+            return Err(format!("Jump target out of range at event {}", index));
+        }
+    }
+    Ok(())
+}
+
+/// Replace `RunTask` events with the referenced task's events, recursively
+/// Jump targets on both sides are remapped so they still point at the same events
+fn expand_task_refs(
+    events: &[ScriptEvent],
+    resolve: &dyn Fn(&str) -> Result<Vec<ScriptEvent>, String>,
+    stack: &mut Vec<String>,
+) -> Result<Vec<ScriptEvent>, String> {
+    if !events
+        .iter()
+        .any(|e| matches!(e, ScriptEvent::RunTask { .. }))
+    {
+        return Ok(events.to_vec());
+    }
+    if stack.len() >= MAX_TASK_DEPTH {
+        return Err(format!(
+            "Tasks nested deeper than {} levels: {}",
+            MAX_TASK_DEPTH,
+            stack.join(" -> ")
+        ));
+    }
+
+    let offset_jumps = |event: &mut ScriptEvent, map: &dyn Fn(usize) -> usize| match event {
+        ScriptEvent::IfPixel {
+            then_jump,
+            else_jump,
+            ..
+        } => {
+            *then_jump = map(*then_jump);
+            *else_jump = map(*else_jump);
+        }
+        ScriptEvent::Jump { target } => *target = map(*target),
+        _ => {}
+    };
+
+    let mut expanded = Vec::with_capacity(events.len());
+    // Output index of each input event, plus one entry for "end of list"
+    let mut new_index = Vec::with_capacity(events.len() + 1);
+    let mut outer_positions = Vec::new();
+
+    for event in events {
+        new_index.push(expanded.len());
+        match event {
+            ScriptEvent::RunTask { id } => {
+                if stack.contains(id) {
+                    return Err(format!(
+                        "Task cycle detected: {} -> {}",
+                        stack.join(" -> "),
+                        id
+                    ));
+                }
+                stack.push(id.clone());
+                let inner = expand_task_refs(&resolve(id)?, resolve, stack)?;
+                stack.pop();
+
+                let base = expanded.len();
+                for mut inner_event in inner {
+                    offset_jumps(&mut inner_event, &|t| base + t);
+                    expanded.push(inner_event);
+                }
+            }
+            _ => {
+                outer_positions.push(expanded.len());
+                expanded.push(event.clone());
+            }
+        }
+    }
+    new_index.push(expanded.len());
+
+    for position in outer_positions {
+        offset_jumps(&mut expanded[position], &|t| {
+            new_index.get(t).copied().unwrap_or(t)
+        });
+    }
+    Ok(expanded)
+}
+
+/// Reject speeds that would break delay math and clamp absurdly high ones
+fn validate_speed(speed_multiplier: f64) -> Result<f64, String> {
+    if !speed_multiplier.is_finite() || speed_multiplier <= 0.0 {
+        return Err(format!(
+            "Invalid speed multiplier: {} (must be greater than 0)",
+            speed_multiplier
+        ));
+    }
+    Ok(speed_multiplier.min(MAX_SPEED_MULTIPLIER))
+}
+
+/// Effective speed for the `executed`-th event of a run
+/// Linearly interpolates from 1x to `target` over the first `ramp_events` events
+fn ramped_speed(target: f64, ramp_events: u32, executed: u32) -> f64 {
+    if executed >= ramp_events {
+        return target;
+    }
+    let progress = executed as f64 / ramp_events as f64;
+    1.0 + (target - 1.0) * progress
+}
+
+/// Per-second jump counter used to throttle tight loops
+struct JumpGuard {
+    window_start: Instant,
+    count: u32,
+}
+
+impl JumpGuard {
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// Record a jump, waiting out the rest of the second once the cap is reached
+    fn record(&mut self) -> Result<(), String> {
+        let window = Duration::from_secs(1);
+        if self.window_start.elapsed() >= window {
+            self.window_start = Instant::now();
+            self.count = 0;
+        }
+
+        self.count += 1;
+        if self.count > MAX_JUMPS_PER_SECOND {
+            while self.window_start.elapsed() < window {
+                if get_state().should_stop() {
+                    return Err("Playback stopped".to_string());
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            self.window_start = Instant::now();
+            self.count = 1;
+        }
+        Ok(())
+    }
+}
+
+/// Sleep for `ms` milliseconds, checking the stop flag periodically
+fn interruptible_sleep(ms: u64) -> Result<(), String> {
+    let chunk_ms = 100; // Check stop every 100ms
+    let mut remaining = ms;
+
+    while remaining > 0 {
+        if get_state().should_stop() {
+            return Err("Playback stopped".to_string());
+        }
+
+        let sleep_time = if remaining > chunk_ms {
+            chunk_ms
+        } else {
+            remaining
+        };
+        thread::sleep(Duration::from_millis(sleep_time));
+        remaining -= sleep_time;
+    }
+    Ok(())
+}
+
+/// Sleep until `deadline`, returning immediately if it has already passed
+fn interruptible_sleep_until(deadline: Instant) -> Result<(), String> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    interruptible_sleep(remaining.as_millis() as u64)
+}
+
+/// Per-run settings that shape how individual events are sent
+#[derive(Debug, Clone)]
+struct EventContext {
+    /// Move to the recorded position before pressing/releasing buttons
+    use_recorded_position: bool,
+    /// Pause between moving to a recorded position and pressing the button
+    click_settle_ms: u64,
+    /// Playback-time display scale divided by record-time display scale
+    coord_scale: f64,
+    /// Whether disabled Delay events still wait
+    honor_disabled_delays: bool,
+    /// Prefer recorded physical key codes over unicode input
+    physical_keys: bool,
+    /// Type symbols and non-ASCII characters through enigo's text API
+    symbols_as_text: bool,
+    /// Screen area coordinates are clamped into, if clamping is enabled
+    bounds: Option<Rect>,
+    /// How far the window of the last WindowAnchor has moved since it was recorded
+    window_offset: Cell<(f64, f64)>,
+}
+
+impl EventContext {
+    /// The enigo key to send, using the physical key code when enabled and recorded
+    fn key(&self, key: &KeyboardKey, key_code: Option<u32>) -> Option<enigo::Key> {
+        match key_code {
+            Some(code) if self.physical_keys => Some(enigo::Key::Other(code)),
+            _ => keyboard_key_to_enigo(key),
+        }
+    }
+
+    /// The character to type as text instead of pressing a key, if any
+    fn text_char(&self, key: &KeyboardKey) -> Option<char> {
+        match key {
+            KeyboardKey::Char(c) if self.symbols_as_text && needs_text_input(*c) => Some(*c),
             _ => None,
-        },
+        }
+    }
+}
+
+/// Whether a character may need layout-specific composition (AltGr, dead keys, Shift)
+fn needs_text_input(c: char) -> bool {
+    !c.is_ascii_alphanumeric() && !c.is_whitespace() && !c.is_control()
+}
+
+/// Factor converting recorded coordinates to the current display's pixels
+/// Only rescales when the record-time scale is known; otherwise coordinates are used as-is
+fn coord_scale(record_scale: Option<f64>, display_scale: f64) -> f64 {
+    match record_scale {
+        Some(record_scale) if record_scale > 0.0 => display_scale / record_scale,
+        _ => 1.0,
+    }
+}
+
+impl EventContext {
+    fn for_script(script: &Script, use_recorded_position: bool) -> Self {
+        Self {
+            use_recorded_position,
+            click_settle_ms: script.click_settle_ms,
+            coord_scale: coord_scale(
+                script.scale_factor,
+                crate::input_manager::display_scale_factor(),
+            ),
+            honor_disabled_delays: script.honor_disabled_delays,
+            physical_keys: script.physical_keys,
+            symbols_as_text: script.symbols_as_text,
+            bounds: if script.clamp_to_screen {
+                crate::input_manager::screen_bounds()
+            } else {
+                None
+            },
+            window_offset: Cell::new((0.0, 0.0)),
+        }
+    }
+
+    /// Convert recorded coordinates to the current display's pixels
+    fn point(&self, x: f64, y: f64) -> (i32, i32) {
+        let (dx, dy) = self.window_offset.get();
+        let point = (
+            ((x + dx) * self.coord_scale).round() as i32,
+            ((y + dy) * self.coord_scale).round() as i32,
+        );
+        match self.bounds {
+            Some(bounds) => {
+                let clamped = bounds.clamp(point.0, point.1);
+                if clamped != point {
+                    log::warn!("Clamped off-screen point {:?} to {:?}", point, clamped);
+                }
+                clamped
+            }
+            None => point,
+        }
+    }
+}
+
+/// Convert a recorded button for enigo, skipping (with a log) buttons it can't press
+fn playable_button(button: MouseButton) -> Option<enigo::Button> {
+    match enigo::Button::try_from(button) {
+        Ok(button) => Some(button),
+        Err(e) => {
+            log::warn!("Skipping mouse event: {}", e);
+            None
+        }
     }
 }
 
 /// Execute a single event
+/// Returns `Some(index)` when the event redirects playback to another event
 fn execute_event(
     enigo: &mut Enigo,
     event: &ScriptEvent,
     speed_multiplier: f64,
-    use_recorded_position: bool,
-) -> Result<(), String> {
+    ctx: &EventContext,
+) -> Result<Option<usize>, String> {
     match event {
         ScriptEvent::Delay { duration_ms } => {
             // Calculate adjusted delay
             let delay_ms = (*duration_ms as f64 / speed_multiplier) as u64;
 
             // Wait for the delay (interruptible)
-            if delay_ms > 0 {
-                let chunk_ms = 100; // Check stop every 100ms
-                let mut remaining = delay_ms;
-
-                while remaining > 0 {
-                    if get_state().should_stop() {
-                        return Err("Playback stopped".to_string());
-                    }
-
-                    let sleep_time = if remaining > chunk_ms {
-                        chunk_ms
-                    } else {
-                        remaining
-                    };
-                    thread::sleep(Duration::from_millis(sleep_time));
-                    remaining -= sleep_time;
-                }
-            }
+            interruptible_sleep(delay_ms)?;
         }
-        ScriptEvent::KeyPress { key } => {
-            if let Some(enigo_key) = keyboard_key_to_enigo(key) {
+        ScriptEvent::KeyPress { key, key_code } => {
+            if let Some(c) = ctx.text_char(key) {
+                // Text input presses and releases in one go; the KeyRelease is skipped
+                enigo
+                    .text(&c.to_string())
+                    .map_err(|e| format!("Text input error: {:?}", e))?;
+            } else if let Some(enigo_key) = ctx.key(key, *key_code) {
                 enigo
                     .key(enigo_key, enigo::Direction::Press)
                     .map_err(|e| format!("Key press error: {:?}", e))?;
             }
         }
-        ScriptEvent::KeyRelease { key } => {
-            if let Some(enigo_key) = keyboard_key_to_enigo(key) {
+        ScriptEvent::KeyRelease { key, key_code } => {
+            if ctx.text_char(key).is_some() {
+                return Ok(None);
+            }
+            if let Some(enigo_key) = ctx.key(key, *key_code) {
                 enigo
                     .key(enigo_key, enigo::Direction::Release)
                     .map_err(|e| format!("Key release error: {:?}", e))?;
             }
         }
         ScriptEvent::MousePress { button, x, y } => {
-            if use_recorded_position {
+            if ctx.use_recorded_position {
                 // Move to position first
+                let (x, y) = ctx.point(*x, *y);
                 enigo
-                    .move_mouse(*x as i32, *y as i32, enigo::Coordinate::Abs)
+                    .move_mouse(x, y, enigo::Coordinate::Abs)
                     .map_err(|e| format!("Mouse move error: {:?}", e))?;
+                // Let slow UIs register the move before the click lands
+                interruptible_sleep(ctx.click_settle_ms)?;
             }
             // Then press
-            enigo
-                .button((*button).into(), enigo::Direction::Press)
-                .map_err(|e| format!("Mouse press error: {:?}", e))?;
+            if let Some(button) = playable_button(*button) {
+                enigo
+                    .button(button, enigo::Direction::Press)
+                    .map_err(|e| format!("Mouse press error: {:?}", e))?;
+            }
         }
         ScriptEvent::MouseRelease { button, x, y } => {
-            if use_recorded_position {
+            if ctx.use_recorded_position {
+                let (x, y) = ctx.point(*x, *y);
                 enigo
-                    .move_mouse(*x as i32, *y as i32, enigo::Coordinate::Abs)
+                    .move_mouse(x, y, enigo::Coordinate::Abs)
                     .map_err(|e| format!("Mouse move error: {:?}", e))?;
             }
-            enigo
-                .button((*button).into(), enigo::Direction::Release)
-                .map_err(|e| format!("Mouse release error: {:?}", e))?;
+            if let Some(button) = playable_button(*button) {
+                enigo
+                    .button(button, enigo::Direction::Release)
+                    .map_err(|e| format!("Mouse release error: {:?}", e))?;
+            }
         }
         ScriptEvent::MouseMove { x, y } => {
+            let (x, y) = ctx.point(*x, *y);
             enigo
-                .move_mouse(*x as i32, *y as i32, enigo::Coordinate::Abs)
+                .move_mouse(x, y, enigo::Coordinate::Abs)
                 .map_err(|e| format!("Mouse move error: {:?}", e))?;
         }
-        ScriptEvent::MouseScroll { delta_x, delta_y } => {
+        ScriptEvent::MouseMoveRel { dx, dy } => {
+            enigo
+                .move_mouse(*dx, *dy, enigo::Coordinate::Rel)
+                .map_err(|e| format!("Mouse move error: {:?}", e))?;
+        }
+        ScriptEvent::MouseScroll {
+            delta_x,
+            delta_y,
+            x,
+            y,
+        } => {
+            // Scroll the pane under the recorded position, not wherever the cursor is now
+            if let (true, Some(x), Some(y)) = (ctx.use_recorded_position, x, y) {
+                let (x, y) = ctx.point(*x, *y);
+                enigo
+                    .move_mouse(x, y, enigo::Coordinate::Abs)
+                    .map_err(|e| format!("Mouse move error: {:?}", e))?;
+            }
             if *delta_y != 0 {
                 enigo
                     .scroll(-*delta_y as i32, enigo::Axis::Vertical)
@@ -220,80 +733,479 @@ fn execute_event(
                     .map_err(|e| format!("Scroll error: {:?}", e))?;
             }
         }
+        ScriptEvent::IfPixel {
+            x,
+            y,
+            rgb,
+            tolerance,
+            then_jump,
+            else_jump,
+        } => {
+            let (x, y) = ctx.point(*x, *y);
+            let color = pixel::get_pixel(x, y)?;
+            let target = if pixel::color_matches(color, *rgb, *tolerance) {
+                *then_jump
+            } else {
+                *else_jump
+            };
+            return Ok(Some(target));
+        }
+        ScriptEvent::WaitForPixelOptional {
+            x,
+            y,
+            rgb,
+            tolerance,
+            max_wait_ms,
+        } => {
+            // Real time, not scaled by speed: it waits on the target app
+            let (x, y) = ctx.point(*x, *y);
+            let deadline = Instant::now() + Duration::from_millis(*max_wait_ms);
+            loop {
+                match pixel::get_pixel(x, y) {
+                    Ok(color) if pixel::color_matches(color, *rgb, *tolerance) => break,
+                    Ok(_) => {}
+                    Err(e) => {
+                        log::warn!("Pixel read failed, not waiting: {}", e);
+                        break;
+                    }
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    log::info!("Pixel at ({}, {}) not ready after {}ms", x, y, max_wait_ms);
+                    break;
+                }
+                interruptible_sleep(PIXEL_POLL_MS.min(remaining.as_millis() as u64))?;
+            }
+        }
+        ScriptEvent::Jump { target } => {
+            return Ok(Some(*target));
+        }
+        ScriptEvent::RunTask { id } => {
+            // play_script expands these before playback starts
+            return Err(format!("Task {} can only run as part of a script", id));
+        }
+        ScriptEvent::Disabled { event } => {
+            if ctx.honor_disabled_delays && matches!(**event, ScriptEvent::Delay { .. }) {
+                execute_event(enigo, event, speed_multiplier, ctx)?;
+            }
+        }
+        ScriptEvent::Label { .. } => {}
+        ScriptEvent::Chord { modifiers, key } => {
+            let send = |enigo: &mut Enigo, key: &KeyboardKey, direction| match keyboard_key_to_enigo(
+                key,
+            ) {
+                Some(enigo_key) => enigo
+                    .key(enigo_key, direction)
+                    .map_err(|e| format!("Chord key error: {:?}", e)),
+                None => Err(format!("Unsupported chord key: {:?}", key)),
+            };
+            let mut held = Vec::with_capacity(modifiers.len());
+            let mut result = Ok(());
+            for modifier in modifiers {
+                result = send(enigo, modifier, enigo::Direction::Press);
+                if result.is_err() {
+                    break;
+                }
+                held.push(modifier);
+            }
+            if result.is_ok() {
+                result = send(enigo, key, enigo::Direction::Click);
+            }
+            // Always let go of whatever was pressed, even after an error
+            for modifier in held.into_iter().rev() {
+                let _ = send(enigo, modifier, enigo::Direction::Release);
+            }
+            result?;
+        }
+        ScriptEvent::CaptureClipboard { var } => {
+            let text = arboard::Clipboard::new()
+                .and_then(|mut clipboard| clipboard.get_text())
+                .map_err(|e| format!("Clipboard error: {}", e))?;
+            get_state().set_var(var, text);
+        }
+        ScriptEvent::TypeVar { var } => {
+            let text = get_state()
+                .get_var(var)
+                .ok_or_else(|| format!("Variable {} was not captured", var))?;
+            enigo
+                .text(&text)
+                .map_err(|e| format!("Text input error: {:?}", e))?;
+        }
+        ScriptEvent::TypeText { text } => {
+            enigo
+                .text(text)
+                .map_err(|e| format!("Text input error: {:?}", e))?;
+        }
+        ScriptEvent::WindowAnchor { title, x, y } => {
+            let offset = match crate::input_manager::foreground_window() {
+                Some((current, wx, wy)) if current == *title => (wx - x, wy - y),
+                _ => {
+                    log::warn!(
+                        "Window \"{}\" is not in the foreground, using recorded coordinates",
+                        title
+                    );
+                    (0.0, 0.0)
+                }
+            };
+            ctx.window_offset.set(offset);
+            get_state().set_target_window(title);
+        }
+        ScriptEvent::KeyRepeat {
+            key,
+            count,
+            interval_ms,
+        } => {
+            if let Some(enigo_key) = keyboard_key_to_enigo(key) {
+                let interval = (*interval_ms as f64 / speed_multiplier) as u64;
+                for i in 0..*count {
+                    if get_state().should_stop() {
+                        return Err("Playback stopped".to_string());
+                    }
+                    enigo
+                        .key(enigo_key, enigo::Direction::Click)
+                        .map_err(|e| format!("Key repeat error: {:?}", e))?;
+                    if i + 1 < *count {
+                        interruptible_sleep(interval)?;
+                    }
+                }
+            }
+        }
     }
 
-    Ok(())
+    Ok(None)
 }
 
 /// Play a script
 pub fn play_script(script: Script) -> Result<(), String> {
-    let state = get_state();
+    submit(script).map(|_| ())
+}
 
-    if state.is_playing() {
-        return Err("Already playing".to_string());
-    }
+/// Play a script and wait until it finishes, is stopped or is replaced by a newer script
+/// For callers without an event loop, like the scheduler; blocks the calling thread
+pub fn play_script_blocking(script: Script) -> Result<(), String> {
+    let ticket = submit(script)?;
+    get_state().wait_for_ticket(ticket);
+    Ok(())
+}
+
+/// Send a script to the playback worker, returning its completion ticket
+fn submit(mut script: Script) -> Result<u64, String> {
+    crate::settings::ensure_automation_enabled()?;
+    let state = get_state();
 
     if script.events.is_empty() {
         return Err("Script has no events".to_string());
     }
 
+    script.events = expand_task_refs(
+        &script.events,
+        &crate::macro_trigger::task_events,
+        &mut Vec::new(),
+    )?;
+    validate_jumps(&script.events)?;
+    script.speed_multiplier = validate_speed(script.speed_multiplier)?;
+
+    log::info!(
+        "Playing script '{}' ({} events, {}x)",
+        script.name,
+        script.events.len(),
+        script.speed_multiplier
+    );
+
+    if !state.claim() {
+        return Err("Already playing".to_string());
+    }
+    // Queue first so a script still winding down sees it and stops before the token reset
+    state.queued.fetch_add(1, Ordering::SeqCst);
     state.start();
+    state.set_loop_count(script.loop_config.count);
+
+    // Tickets are taken under the sender lock so they follow the worker's order
+    let worker = PLAYBACK_WORKER.lock();
+    if worker.send(script).is_err() {
+        state.queued.fetch_sub(1, Ordering::SeqCst);
+        state.finish();
+        return Err("Playback worker unavailable".to_string());
+    }
+    Ok(state.submitted.fetch_add(1, Ordering::SeqCst) + 1)
+}
+
+/// Spawn the playback worker thread
+/// The worker owns a single Enigo, created on first use and reused for every script
+fn spawn_worker() -> mpsc::Sender<Script> {
+    let (sender, receiver) = mpsc::channel::<Script>();
 
     thread::spawn(move || {
-        let state = get_state();
-        let settings = Settings::default();
-        let mut enigo = match Enigo::new(&settings) {
-            Ok(e) => e,
-            Err(e) => {
-                eprintln!("Failed to create Enigo: {:?}", e);
-                state.finish();
-                return;
+        let mut enigo: Option<Enigo> = None;
+
+        for script in receiver {
+            get_state().queued.fetch_sub(1, Ordering::SeqCst);
+
+            if enigo.is_none() {
+                match Enigo::new(&Settings::default()) {
+                    Ok(e) => enigo = Some(e),
+                    Err(e) => {
+                        log::error!("Failed to create Enigo: {:?}", e);
+                        get_state().finish();
+                        get_state().mark_completed();
+                        continue;
+                    }
+                }
             }
-        };
 
-        let loop_count = script.loop_config.count;
-        let is_infinite = loop_count == 0;
+            if let Some(enigo) = enigo.as_mut() {
+                let origin = if script.restore_cursor {
+                    enigo.location().ok()
+                } else {
+                    None
+                };
+                run_script(enigo, &script);
+                // A queued script takes over the cursor, so leave it alone
+                if let Some((x, y)) = origin {
+                    if get_state().queued.load(Ordering::SeqCst) == 0 {
+                        if let Err(e) = enigo.move_mouse(x, y, enigo::Coordinate::Abs) {
+                            log::warn!("Failed to restore cursor: {:?}", e);
+                        }
+                    }
+                }
+            }
+            get_state().mark_completed();
+        }
+    });
 
-        // Check if script has any mouse move events
-        // If no mouse moves are present, we use the current mouse position for clicks
-        // instead of the recorded coordinates (which might be 0,0)
-        let has_mouse_moves = script
-            .events
-            .iter()
-            .any(|e| matches!(e, ScriptEvent::MouseMove { .. }));
+    sender
+}
 
-        loop {
-            let current_iteration = state.increment_loop();
+/// Run a script to completion on the worker thread
+fn run_script(enigo: &mut Enigo, script: &Script) {
+    let state = get_state();
 
-            // Check if we should stop (loop count reached or stop requested)
-            if !is_infinite && current_iteration > loop_count {
-                break;
-            }
+    // Check if script has any mouse move events
+    // If no mouse moves are present, we use the current mouse position for clicks
+    // instead of the recorded coordinates (which might be 0,0)
+    let has_mouse_moves = script
+        .events
+        .iter()
+        .any(|e| matches!(e, ScriptEvent::MouseMove { .. }));
+    let ctx = EventContext::for_script(script, has_mouse_moves);
+    let _watchdog = script.watch_target_window.then(spawn_watchdog);
 
-            if state.should_stop() {
+    let mut executed = 0;
+
+    loop {
+        let current_iteration = state.increment_loop();
+
+        // Check if we should stop (loop count reached or stop requested)
+        // The count is read every iteration, since set_remaining_loops may change it
+        if state
+            .target_loops()
+            .is_some_and(|target| current_iteration > target)
+        {
+            break;
+        }
+
+        if state.should_stop() {
+            break;
+        }
+
+        // Execute events, following jumps produced by branch events
+        let mut index = 0;
+        let mut jump_guard = JumpGuard::new();
+        // Absolute timing: when this loop started and how much scaled delay it has scheduled
+        let loop_start = Instant::now();
+        let mut timeline_ms = 0.0;
+        while index < script.events.len() {
+            state.set_event_index(index);
+            if !state.wait_for_step() {
                 break;
             }
 
-            // Execute all events
-            for (index, event) in script.events.iter().enumerate() {
-                state.set_event_index(index);
+            let speed = ramped_speed(script.speed_multiplier, script.ramp_events, executed);
+            executed = executed.saturating_add(1);
 
-                if let Err(e) =
-                    execute_event(&mut enigo, event, script.speed_multiplier, has_mouse_moves)
-                {
-                    eprintln!("Playback error: {}", e);
+            let event = &script.events[index];
+            let result = match event {
+                ScriptEvent::Delay { duration_ms } if script.absolute_timing => {
+                    timeline_ms += *duration_ms as f64 / speed;
+                    let deadline = loop_start + Duration::from_secs_f64(timeline_ms / 1000.0);
+                    interruptible_sleep_until(deadline).map(|_| None)
+                }
+                _ => execute_event(enigo, event, speed, &ctx),
+            };
+            let result = result.and_then(|jump| {
+                // Give the target app breathing room after each input
+                if event.is_input() {
+                    interruptible_sleep(script.global_event_padding_ms)?;
+                }
+                Ok(jump)
+            });
+
+            match result {
+                Ok(Some(target)) => {
+                    if let Err(e) = jump_guard.record() {
+                        log::error!("Playback error: {}", e);
+                        state.finish();
+                        return;
+                    }
+                    index = target;
+                }
+                Ok(None) => index += 1,
+                Err(e) => {
+                    log::error!("Playback error: {}", e);
                     state.finish();
                     return;
                 }
+            }
 
-                if state.should_stop() {
-                    break;
-                }
+            if state.should_stop() {
+                break;
             }
+        }
+
+        // Delay between loops
+        if script.loop_config.delay_between_ms > 0 && !state.should_stop() {
+            thread::sleep(Duration::from_millis(script.loop_config.delay_between_ms));
+        }
+    }
+
+    state.finish();
+}
+
+/// Counts how long the window of the last WindowAnchor has been out of the foreground
+#[derive(Debug, Default)]
+struct TargetWatch {
+    misses: u32,
+}
 
-            // Delay between loops
-            if script.loop_config.delay_between_ms > 0 && !state.should_stop() {
-                thread::sleep(Duration::from_millis(script.loop_config.delay_between_ms));
+impl TargetWatch {
+    /// Record one check, returning true once the target has been missing `WATCHDOG_MISSES` times
+    /// No target yet, or a foreground window that can't be queried, counts as present
+    fn observe(&mut self, target: Option<&str>, foreground: Option<&str>) -> bool {
+        match (target, foreground) {
+            (Some(target), Some(foreground)) if target != foreground => self.misses += 1,
+            _ => self.misses = 0,
+        }
+        self.misses >= WATCHDOG_MISSES
+    }
+}
+
+/// Ends the watchdog thread when the run it watches ends
+struct WatchdogHandle(Arc<AtomicBool>);
+
+impl Drop for WatchdogHandle {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Poll the foreground window during a run, stopping playback if the target window is lost
+fn spawn_watchdog() -> WatchdogHandle {
+    let active = Arc::new(AtomicBool::new(true));
+    let watching = Arc::clone(&active);
+
+    thread::spawn(move || {
+        let state = get_state();
+        let mut watch = TargetWatch::default();
+        loop {
+            thread::sleep(WATCHDOG_INTERVAL);
+            if !watching.load(Ordering::SeqCst) || state.should_stop() {
+                break;
+            }
+            // Whoever is stepping is looking at the app, not the target
+            if state.is_stepping() {
+                watch = TargetWatch::default();
+                continue;
+            }
+
+            let target = state.target_window();
+            let foreground = crate::input_manager::foreground_window().map(|(title, _, _)| title);
+            if watch.observe(target.as_deref(), foreground.as_deref()) {
+                log::warn!(
+                    "Target window \"{}\" is gone, stopping playback",
+                    target.unwrap_or_default()
+                );
+                state.stop();
+                crate::input_manager::emit_event(
+                    "hotkey-event",
+                    crate::hotkey::HotkeyEvent {
+                        action: "playback-target-lost".to_string(),
+                        recording: false,
+                        playing: false,
+                    },
+                );
+                break;
+            }
+        }
+    });
+
+    WatchdogHandle(active)
+}
+
+/// Cursor position sent to the overlay by `visualize_script`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VisualizePoint {
+    /// Position in logical (CSS) pixels
+    pub x: f64,
+    pub y: f64,
+    /// Whether a mouse button is held at this point
+    pub pressed: bool,
+}
+
+/// Replay a script's cursor path as `visualize-point` events, timed like playback
+/// No input is sent; jumps and loops are not followed
+pub fn visualize_script(script: Script) -> Result<(), String> {
+    crate::settings::ensure_automation_enabled()?;
+    let state = get_state();
+
+    if !state.claim() {
+        return Err("Already playing".to_string());
+    }
+
+    state.start();
+    thread::spawn(move || {
+        let state = get_state();
+        // Physical pixels to the overlay's logical ones; unknown scales were recorded on this display
+        let scale = match script.scale_factor {
+            Some(scale) if scale > 0.0 => scale,
+            _ => crate::input_manager::display_scale_factor(),
+        };
+        let mut held = 0usize;
+
+        for (index, event) in script.events.iter().enumerate() {
+            if state.should_stop() {
+                break;
+            }
+            state.set_event_index(index);
+
+            let point = match event {
+                ScriptEvent::Delay { duration_ms } => {
+                    let delay_ms = (*duration_ms as f64 / script.speed_multiplier) as u64;
+                    if interruptible_sleep(delay_ms).is_err() {
+                        break;
+                    }
+                    None
+                }
+                ScriptEvent::MouseMove { x, y } => Some((*x, *y)),
+                ScriptEvent::MousePress { x, y, .. } => {
+                    held += 1;
+                    Some((*x, *y))
+                }
+                ScriptEvent::MouseRelease { x, y, .. } => {
+                    held = held.saturating_sub(1);
+                    Some((*x, *y))
+                }
+                _ => None,
+            };
+
+            if let Some((x, y)) = point {
+                crate::input_manager::emit_event(
+                    "visualize-point",
+                    VisualizePoint {
+                        x: x / scale,
+                        y: y / scale,
+                        pressed: held > 0,
+                    },
+                );
             }
         }
 
@@ -303,16 +1215,172 @@ pub fn play_script(script: Script) -> Result<(), String> {
     Ok(())
 }
 
+/// Execute one event immediately on the calling thread
+/// Delays are skipped and recorded mouse positions are always used
+pub fn execute_single_event(event: ScriptEvent) -> Result<(), String> {
+    crate::settings::ensure_automation_enabled()?;
+    let state = get_state();
+
+    if state.is_playing() {
+        return Err("Already playing".to_string());
+    }
+
+    if matches!(event, ScriptEvent::Delay { .. }) {
+        return Ok(());
+    }
+
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+
+    // Mark as playing so the input listener ignores the simulated input
+    if !state.claim() {
+        return Err("Already playing".to_string());
+    }
+    state.start();
+    let ctx = EventContext {
+        use_recorded_position: true,
+        click_settle_ms: 0,
+        coord_scale: 1.0,
+        honor_disabled_delays: false,
+        physical_keys: false,
+        symbols_as_text: false,
+        bounds: None,
+        window_offset: Cell::new((0.0, 0.0)),
+    };
+    let result = execute_event(&mut enigo, &event, 1.0, &ctx).map(|_| ());
+    state.is_playing.store(false, Ordering::SeqCst);
+
+    result
+}
+
+/// Refuse an infinite loop (`count == 0`) unless the caller explicitly confirmed it
+pub fn ensure_infinite_confirmed(
+    loop_config: &LoopConfig,
+    confirm_infinite: Option<bool>,
+) -> Result<(), String> {
+    if loop_config.count == 0 && !confirm_infinite.unwrap_or(false) {
+        return Err("infinite loop requires confirmation".to_string());
+    }
+    Ok(())
+}
+
+/// Current cursor position in screen pixels
+pub fn cursor_position() -> Result<(i32, i32), String> {
+    let enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+    enigo
+        .location()
+        .map_err(|e| format!("Cursor position error: {:?}", e))
+}
+
+/// Key presses that type `text`, spaced to `wpm` words (of five characters) per minute
+fn text_events(text: &str, wpm: Option<f64>) -> Result<Vec<ScriptEvent>, String> {
+    let interval_ms = match wpm {
+        Some(wpm) if !wpm.is_finite() || wpm <= 0.0 => {
+            return Err(format!("Invalid typing speed: {}", wpm));
+        }
+        Some(wpm) => (60_000.0 / (wpm * 5.0)).round() as u64,
+        None => 0,
+    };
+
+    let mut events = Vec::new();
+    for c in text.chars() {
+        let key = match c {
+            '\n' => KeyboardKey::Special("Return".to_string()),
+            '\t' => KeyboardKey::Special("Tab".to_string()),
+            '\r' => continue,
+            c => KeyboardKey::Char(c),
+        };
+        if interval_ms > 0 && !events.is_empty() {
+            events.push(ScriptEvent::Delay {
+                duration_ms: interval_ms,
+            });
+        }
+        events.push(ScriptEvent::KeyPress {
+            key: key.clone(),
+            key_code: None,
+        });
+        events.push(ScriptEvent::KeyRelease {
+            key,
+            key_code: None,
+        });
+    }
+    Ok(events)
+}
+
+/// Type a string through the playback worker, so stop keys and the overlay apply
+pub fn type_text(text: &str, wpm: Option<f64>) -> Result<(), String> {
+    let script = Script {
+        name: "Type text".to_string(),
+        events: text_events(text, wpm)?,
+        symbols_as_text: true,
+        ..Default::default()
+    };
+    play_script(script)
+}
+
 /// Play a list of events (without Script wrapper)
-pub fn play_events(events: Vec<ScriptEvent>, speed_multiplier: f64) -> Result<(), String> {
+/// Plays once unless `loop_config` is given
+pub fn play_events(
+    events: Vec<ScriptEvent>,
+    speed_multiplier: f64,
+    loop_config: Option<LoopConfig>,
+) -> Result<(), String> {
     let script = Script {
         events,
-        speed_multiplier,
+        speed_multiplier: validate_speed(speed_multiplier)?,
+        loop_config: loop_config.unwrap_or(LoopConfig {
+            count: 1,
+            delay_between_ms: 0,
+        }),
         ..Default::default()
     };
     play_script(script)
 }
 
+/// Play a script in step mode: nothing runs until `step_playback` is called,
+/// then exactly one event runs per call
+pub fn play_script_stepping(script: Script) -> Result<(), String> {
+    let state = get_state();
+    if state.is_playing() {
+        return Err("Already playing".to_string());
+    }
+    state.set_stepping(true);
+    play_script(script).inspect_err(|_| state.set_stepping(false))
+}
+
+/// Run the next event of a playback in step mode
+pub fn step_playback() -> Result<(), String> {
+    let state = get_state();
+    if !state.is_playing() {
+        return Err("Not playing".to_string());
+    }
+    if !state.is_stepping() {
+        return Err("Playback is not in step mode".to_string());
+    }
+    state.step();
+    Ok(())
+}
+
+/// Loop iterations left after the current one (None for an infinite run)
+pub fn get_remaining_loops() -> Result<Option<u32>, String> {
+    let state = get_state();
+    if !state.is_playing() {
+        return Err("Not playing".to_string());
+    }
+    Ok(state.remaining_loops())
+}
+
+/// Extend or shorten the running playback to `count` iterations after the current one
+pub fn set_remaining_loops(count: u32) -> Result<(), String> {
+    let state = get_state();
+    if !state.is_playing() {
+        return Err("Not playing".to_string());
+    }
+    state.set_remaining_loops(count);
+    Ok(())
+}
+
 /// Stop playback
 pub fn stop_playback() {
     get_state().stop();
@@ -322,3 +1390,187 @@ pub fn stop_playback() {
 pub fn is_playing() -> bool {
     get_state().is_playing()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jump(target: usize) -> ScriptEvent {
+        ScriptEvent::Jump { target }
+    }
+
+    fn run_task(id: &str) -> ScriptEvent {
+        ScriptEvent::RunTask { id: id.to_string() }
+    }
+
+    #[test]
+    fn test_coord_scale_needs_known_record_scale() {
+        let script = Script::default();
+        assert_eq!(script.scale_factor, None);
+        assert_eq!(coord_scale(script.scale_factor, 1.5), 1.0);
+        assert_eq!(coord_scale(Some(1.0), 1.5), 1.5);
+        assert_eq!(coord_scale(Some(2.0), 1.0), 0.5);
+        assert_eq!(coord_scale(Some(0.0), 1.5), 1.0);
+    }
+
+    #[test]
+    fn test_special_key_names_are_playable() {
+        for name in special_key_names() {
+            assert!(keyboard_key_to_enigo(&KeyboardKey::Special(name.clone())).is_some());
+        }
+        assert!(keyboard_key_to_enigo(&KeyboardKey::Special("Bogus".to_string())).is_none());
+    }
+
+    #[test]
+    fn test_text_events_spacing() {
+        let events = text_events("a\n", Some(60.0)).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                ScriptEvent::KeyPress {
+                    key: KeyboardKey::Char('a'),
+                    key_code: None,
+                },
+                ScriptEvent::KeyRelease {
+                    key: KeyboardKey::Char('a'),
+                    key_code: None,
+                },
+                ScriptEvent::Delay { duration_ms: 200 },
+                ScriptEvent::KeyPress {
+                    key: KeyboardKey::Special("Return".to_string()),
+                    key_code: None,
+                },
+                ScriptEvent::KeyRelease {
+                    key: KeyboardKey::Special("Return".to_string()),
+                    key_code: None,
+                },
+            ]
+        );
+        assert!(text_events("a", Some(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_infinite_loop_needs_confirmation() {
+        let infinite = LoopConfig {
+            count: 0,
+            delay_between_ms: 0,
+        };
+        assert!(ensure_infinite_confirmed(&infinite, None).is_err());
+        assert!(ensure_infinite_confirmed(&infinite, Some(true)).is_ok());
+        assert!(ensure_infinite_confirmed(&LoopConfig::default(), None).is_ok());
+    }
+
+    #[test]
+    fn test_wait_for_step_takes_one_permit() {
+        let state = PlaybackState::new();
+        assert!(state.wait_for_step());
+
+        state.set_stepping(true);
+        state.step();
+        assert!(state.wait_for_step());
+        assert_eq!(*state.step_permits.lock(), 0);
+
+        // With no permit left, only a stop ends the wait
+        state.stop_token.cancel();
+        assert!(!state.wait_for_step());
+    }
+
+    #[test]
+    fn test_target_watch_needs_consecutive_misses() {
+        let mut watch = TargetWatch::default();
+        // Nothing anchored yet, or the foreground can't be read
+        assert!(!watch.observe(None, Some("Other")));
+        assert!(!watch.observe(Some("App"), None));
+
+        for _ in 1..WATCHDOG_MISSES {
+            assert!(!watch.observe(Some("App"), Some("Other")));
+        }
+        // The target coming back resets the count
+        assert!(!watch.observe(Some("App"), Some("App")));
+        for _ in 1..WATCHDOG_MISSES {
+            assert!(!watch.observe(Some("App"), Some("Other")));
+        }
+        assert!(watch.observe(Some("App"), Some("Other")));
+    }
+
+    #[test]
+    fn test_claim_is_exclusive() {
+        let state = PlaybackState::new();
+        assert!(state.claim());
+        assert!(!state.claim());
+
+        state.stop();
+        assert!(state.claim());
+    }
+
+    #[test]
+    fn test_remaining_loops_follow_updates() {
+        let state = PlaybackState::new();
+        state.set_loop_count(3);
+        state.increment_loop();
+        assert_eq!(state.remaining_loops(), Some(2));
+
+        state.set_remaining_loops(5);
+        assert_eq!(state.target_loops(), Some(6));
+
+        state.set_loop_count(0);
+        assert_eq!(state.remaining_loops(), None);
+        state.set_remaining_loops(0);
+        assert_eq!(state.target_loops(), Some(1));
+    }
+
+    #[test]
+    fn test_needs_text_input() {
+        assert!(needs_text_input('@'));
+        assert!(needs_text_input('{'));
+        assert!(needs_text_input('é'));
+        assert!(!needs_text_input('a'));
+        assert!(!needs_text_input('7'));
+        assert!(!needs_text_input(' '));
+    }
+
+    #[test]
+    fn test_expand_task_refs_remaps_jumps() {
+        let resolve = |id: &str| match id {
+            "inner" => Ok(vec![jump(1), ScriptEvent::Delay { duration_ms: 5 }]),
+            _ => Err(format!("Task not found: {}", id)),
+        };
+        let events = vec![run_task("inner"), jump(0), jump(3)];
+
+        let expanded = expand_task_refs(&events, &resolve, &mut Vec::new()).unwrap();
+        assert_eq!(
+            expanded,
+            vec![
+                jump(1),
+                ScriptEvent::Delay { duration_ms: 5 },
+                jump(0),
+                jump(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_task_refs_rejects_cycles() {
+        let resolve = |id: &str| match id {
+            "a" => Ok(vec![run_task("b")]),
+            _ => Ok(vec![run_task("a")]),
+        };
+        let result = expand_task_refs(&[run_task("a")], &resolve, &mut Vec::new());
+        assert!(result.unwrap_err().contains("cycle"));
+    }
+
+    #[test]
+    fn test_validate_speed_rejects_non_positive() {
+        assert!(validate_speed(0.0).is_err());
+        assert!(validate_speed(-1.0).is_err());
+        assert!(validate_speed(f64::NAN).is_err());
+        assert!(validate_speed(f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_validate_speed_clamps_high_values() {
+        assert_eq!(validate_speed(0.1), Ok(0.1));
+        assert_eq!(validate_speed(100.0), Ok(100.0));
+        assert_eq!(validate_speed(250.0), Ok(MAX_SPEED_MULTIPLIER));
+    }
+}
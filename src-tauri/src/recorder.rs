@@ -1,38 +1,182 @@
 //! Recording module - captures keyboard and mouse events
 //! State management only (event loop moved to input_manager)
 
-use crate::script::ScriptEvent;
+use crate::script::{KeyboardKey, ScriptEvent};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Minimum interval between live `recorded-event` emissions for mouse moves
+const MOUSE_MOVE_EMIT_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Global recording state
 static RECORDING_STATE: Lazy<Arc<RecordingState>> = Lazy::new(|| Arc::new(RecordingState::new()));
 
+/// How key events are captured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyCaptureMode {
+    /// Record raw keys, including separate Shift presses
+    #[default]
+    Raw,
+    /// Fold Shift into the produced character (e.g. Shift+a is recorded as 'A')
+    Resolved,
+}
+
+/// Options controlling how a recording is captured
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RecordingOptions {
+    /// Stamp every event with this fixed delay instead of the measured elapsed time
+    #[serde(default)]
+    pub fixed_interval_ms: Option<u64>,
+    /// Raw keys or resolved characters
+    #[serde(default)]
+    pub key_mode: KeyCaptureMode,
+    /// Append events to a JSON-lines file instead of holding them in memory
+    #[serde(default)]
+    pub stream_to_file: bool,
+    /// Record mouse movement as relative deltas instead of absolute positions
+    #[serde(default)]
+    pub relative_mouse: bool,
+    /// Mark the recording idle after this long without input
+    /// The delay before the next event is clamped to this value
+    #[serde(default)]
+    pub idle_timeout_ms: Option<u64>,
+    /// Stop recording when this key is pressed; the key itself is not recorded
+    #[serde(default)]
+    pub stop_key: Option<KeyboardKey>,
+    /// Drop a repeated press of the same key arriving within this many ms, with no release
+    /// in between; cleans up keyboards that bounce, but can eat deliberately fast input
+    #[serde(default)]
+    pub dedup_window_ms: Option<u64>,
+    /// Record IME-composed text as one TypeText event instead of the raw composing keys
+    /// Frontend recordings only: compositions are seen in the AutoKB window alone, and the
+    /// global hook would record the composing keys as well, typing the text twice
+    #[serde(default)]
+    pub ime_text: bool,
+    /// Record WindowAnchor events so mouse coordinates follow the foreground window if it moves
+    #[serde(default)]
+    pub window_relative: bool,
+}
+
+/// File sink for streaming recordings
+struct EventStream {
+    writer: BufWriter<File>,
+    /// Number of events written so far
+    len: usize,
+}
+
+/// Path of the JSON-lines file used by streaming recordings
+/// Kept after the recording stops so it can be recovered
+pub fn stream_path() -> PathBuf {
+    std::env::temp_dir().join("autokb-recording.jsonl")
+}
+
+/// Read all events from the stream file
+fn read_stream_file() -> Vec<ScriptEvent> {
+    let content = match fs::read_to_string(stream_path()) {
+        Ok(content) => content,
+        Err(e) => {
+            log::error!("Failed to read recording stream: {}", e);
+            return Vec::new();
+        }
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Time source for the recorder, so tests can control elapsed time
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The real monotonic clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
 /// Recording state manager
 pub struct RecordingState {
+    /// Source of timestamps for delays and throttling
+    clock: Arc<dyn Clock>,
     /// Whether recording is active
     is_recording: AtomicBool,
     /// Recorded events
     events: Mutex<Vec<ScriptEvent>>,
+    /// File sink while a streaming recording is active
+    stream: Mutex<Option<EventStream>>,
+    /// Options for the current recording
+    options: Mutex<RecordingOptions>,
     /// Recording start time
     start_time: Mutex<Option<Instant>>,
     /// Last event time
     last_event_time: Mutex<Option<Instant>>,
     /// Current mouse position
     mouse_position: Mutex<(f64, f64)>,
+    /// Last time a mouse move was emitted to the frontend
+    last_move_emit: Mutex<Option<Instant>>,
+    /// Display scale factor when the recording started
+    scale_factor: Mutex<f64>,
+    /// Position the last relative move was measured to
+    relative_anchor: Mutex<Option<(f64, f64)>>,
+    /// Set once no input arrived within the idle timeout
+    idle: AtomicBool,
+    /// Incremented on every start, so idle watchers of old recordings exit
+    session: AtomicU64,
+    /// Markers added so far in the current recording
+    markers: AtomicU32,
+    /// Events come only from `record_frontend_event`; the global hook is ignored
+    frontend_only: AtomicBool,
+    /// Last key pressed and when, cleared when it is released; used by the dedup window
+    last_key_press: Mutex<Option<(KeyboardKey, Instant)>>,
+    /// Title and position of the window the last WindowAnchor was recorded for
+    last_window: Mutex<Option<(String, f64, f64)>>,
+}
+
+/// Payload of the `recorded-event` event, emitted as events are committed
+#[derive(Clone, serde::Serialize)]
+pub struct RecordedEvent {
+    /// Position of the event in the recording buffer
+    pub index: usize,
+    pub event: ScriptEvent,
 }
 
 impl RecordingState {
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
         Self {
+            clock,
             is_recording: AtomicBool::new(false),
             events: Mutex::new(Vec::new()),
+            stream: Mutex::new(None),
+            options: Mutex::new(RecordingOptions::default()),
             start_time: Mutex::new(None),
             last_event_time: Mutex::new(None),
             mouse_position: Mutex::new((0.0, 0.0)),
+            last_move_emit: Mutex::new(None),
+            scale_factor: Mutex::new(1.0),
+            relative_anchor: Mutex::new(None),
+            idle: AtomicBool::new(false),
+            session: AtomicU64::new(0),
+            markers: AtomicU32::new(0),
+            frontend_only: AtomicBool::new(false),
+            last_key_press: Mutex::new(None),
+            last_window: Mutex::new(None),
         }
     }
 
@@ -40,24 +184,154 @@ impl RecordingState {
         self.is_recording.load(Ordering::SeqCst)
     }
 
-    pub fn start(&self) {
+    pub fn start(&self, options: RecordingOptions) {
         self.events.lock().clear();
-        *self.start_time.lock() = Some(Instant::now());
-        *self.last_event_time.lock() = Some(Instant::now());
+        *self.stream.lock() = if options.stream_to_file {
+            match File::create(stream_path()) {
+                Ok(file) => Some(EventStream {
+                    writer: BufWriter::new(file),
+                    len: 0,
+                }),
+                Err(e) => {
+                    log::error!(
+                        "Failed to create recording stream, keeping events in memory: {}",
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let idle_timeout_ms = options.idle_timeout_ms;
+        *self.options.lock() = options;
+        *self.scale_factor.lock() = crate::input_manager::display_scale_factor();
+        *self.relative_anchor.lock() = None;
+        *self.last_key_press.lock() = None;
+        *self.last_window.lock() = None;
+        *self.start_time.lock() = Some(self.clock.now());
+        *self.last_event_time.lock() = Some(self.clock.now());
+        self.idle.store(false, Ordering::SeqCst);
+        self.markers.store(0, Ordering::SeqCst);
+        let session = self.session.fetch_add(1, Ordering::SeqCst) + 1;
         self.is_recording.store(true, Ordering::SeqCst);
+
+        if let Some(timeout_ms) = idle_timeout_ms {
+            spawn_idle_watcher(session, timeout_ms);
+        }
+    }
+
+    /// Start a recording fed only by frontend events, for when global hooks don't work
+    pub fn start_frontend(&self, options: RecordingOptions) {
+        // Set before recording starts so the global hook never sees an active recording
+        self.frontend_only.store(true, Ordering::SeqCst);
+        self.start(options);
+    }
+
+    /// Whether the current recording ignores the global input hook
+    pub fn is_frontend_only(&self) -> bool {
+        self.frontend_only.load(Ordering::SeqCst)
+    }
+
+    /// Stop recording, returning false if it wasn't running
+    pub fn stop(&self) -> bool {
+        if !self.is_recording.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        self.frontend_only.store(false, Ordering::SeqCst);
+
+        // Finalize a streaming recording into the in-memory buffer
+        if let Some(mut stream) = self.stream.lock().take() {
+            let _ = stream.writer.flush();
+            *self.events.lock() = read_stream_file();
+        }
+        true
+    }
+
+    /// Discard everything recorded so far and restart timing from now, keeping the recording running
+    /// Returns false if not recording
+    pub fn clear(&self) -> bool {
+        if !self.is_recording() {
+            return false;
+        }
+        self.events.lock().clear();
+        if let Some(stream) = self.stream.lock().as_mut() {
+            match File::create(stream_path()) {
+                Ok(file) => {
+                    stream.writer = BufWriter::new(file);
+                    stream.len = 0;
+                }
+                Err(e) => log::error!("Failed to reset recording stream: {}", e),
+            }
+        }
+        *self.relative_anchor.lock() = None;
+        *self.last_key_press.lock() = None;
+        *self.last_window.lock() = None;
+        *self.last_event_time.lock() = Some(self.clock.now());
+        self.idle.store(false, Ordering::SeqCst);
+        self.markers.store(0, Ordering::SeqCst);
+        true
+    }
+
+    pub fn options(&self) -> RecordingOptions {
+        self.options.lock().clone()
     }
 
-    pub fn stop(&self) {
-        self.is_recording.store(false, Ordering::SeqCst);
+    pub fn scale_factor(&self) -> f64 {
+        *self.scale_factor.lock()
     }
 
     pub fn get_events(&self) -> Vec<ScriptEvent> {
+        if let Some(stream) = self.stream.lock().as_mut() {
+            let _ = stream.writer.flush();
+            return read_stream_file();
+        }
         self.events.lock().clone()
     }
 
+    /// Store committed events in the stream file or memory buffer
+    /// Returns the buffer index of the first stored event
+    fn store(&self, new_events: &[ScriptEvent]) -> usize {
+        if let Some(stream) = self.stream.lock().as_mut() {
+            let first_index = stream.len;
+            for event in new_events {
+                let result = serde_json::to_string(event)
+                    .map_err(|e| e.to_string())
+                    .and_then(|line| {
+                        writeln!(stream.writer, "{}", line).map_err(|e| e.to_string())
+                    });
+                if let Err(e) = result {
+                    log::error!("Failed to write recording stream: {}", e);
+                }
+            }
+            let _ = stream.writer.flush();
+            stream.len += new_events.len();
+            return first_index;
+        }
+
+        let mut events = self.events.lock();
+        let first_index = events.len();
+        events.extend_from_slice(new_events);
+        first_index
+    }
+
+    /// Elapsed ms since the recording started and number of events recorded so far
+    pub fn status(&self) -> (u64, usize) {
+        let elapsed = self
+            .start_time
+            .lock()
+            .map(|t| self.clock.now().duration_since(t).as_millis() as u64)
+            .unwrap_or(0);
+        let count = match self.stream.lock().as_ref() {
+            Some(stream) => stream.len,
+            None => self.events.lock().len(),
+        };
+        (elapsed, count)
+    }
+
     pub fn get_elapsed_ms(&self) -> u64 {
         let last_time = self.last_event_time.lock();
-        let now = Instant::now();
+        let now = self.clock.now();
         last_time
             .map(|t| now.duration_since(t).as_millis() as u64)
             .unwrap_or(0)
@@ -68,22 +342,123 @@ impl RecordingState {
             return;
         }
 
-        // Calculate elapsed time since last event
-        let elapsed = self.get_elapsed_ms();
+        // Calculate elapsed time since last event (or use the fixed interval)
+        let options = self.options.lock().clone();
+        let mut elapsed = match options.fixed_interval_ms {
+            Some(interval) => interval,
+            None => self.get_elapsed_ms(),
+        };
+
+        // Resuming from idle: don't record the whole gap
+        // A gap that ends in a release is a hold, though, and must keep its length
+        let is_release = matches!(
+            event,
+            ScriptEvent::MouseRelease { .. } | ScriptEvent::KeyRelease { .. }
+        );
+        if self.idle.swap(false, Ordering::SeqCst) && !is_release {
+            if let Some(timeout_ms) = options.idle_timeout_ms {
+                elapsed = elapsed.min(timeout_ms);
+            }
+        }
 
         // Update time
         let mut last_time = self.last_event_time.lock();
-        *last_time = Some(Instant::now());
+        *last_time = Some(self.clock.now());
 
-        let mut events = self.events.lock();
+        let mut committed = Vec::with_capacity(2);
         // Insert Delay event if there's significant elapsed time
         if elapsed > 0 {
-            events.push(ScriptEvent::Delay {
+            committed.push(ScriptEvent::Delay {
                 duration_ms: elapsed,
             });
         }
         // Add actual action event
-        events.push(event);
+        committed.push(event);
+
+        let first_index = self.store(&committed);
+        for (offset, event) in committed.into_iter().enumerate() {
+            let index = first_index + offset;
+            if self.should_emit(&event) {
+                crate::input_manager::emit_event("recorded-event", RecordedEvent { index, event });
+            }
+        }
+    }
+
+    /// Whether `event` is a bounced duplicate key press that the dedup window should drop
+    /// Releases must be passed to `note_key_release` so a real re-press isn't dropped
+    pub fn is_duplicate_key_press(&self, event: &ScriptEvent) -> bool {
+        let ScriptEvent::KeyPress { key, .. } = event else {
+            return false;
+        };
+        let Some(window_ms) = self.options.lock().dedup_window_ms else {
+            return false;
+        };
+        let now = self.clock.now();
+        let mut last = self.last_key_press.lock();
+        if let Some((last_key, at)) = last.as_ref() {
+            if last_key == key && now.duration_since(*at) < Duration::from_millis(window_ms) {
+                return true;
+            }
+        }
+        *last = Some((key.clone(), now));
+        false
+    }
+
+    /// Forget the dedup window's last press if `key` was it, so its next press is recorded
+    pub fn note_key_release(&self, key: &KeyboardKey) {
+        let mut last = self.last_key_press.lock();
+        if last.as_ref().is_some_and(|(last_key, _)| last_key == key) {
+            *last = None;
+        }
+    }
+
+    /// Commit text committed by an IME composition, if the recording asked for it
+    /// Ignored outside frontend recordings, whose global hook already records the keys
+    pub fn commit_composition(&self, text: String) {
+        if text.is_empty() || !self.is_frontend_only() || !self.options.lock().ime_text {
+            return;
+        }
+        self.commit_event(ScriptEvent::TypeText { text });
+    }
+
+    /// Commit a WindowAnchor if the foreground window differs from the last one recorded
+    pub fn note_window(&self, window: Option<(String, f64, f64)>) {
+        let Some(window) = window else {
+            return;
+        };
+        let mut last = self.last_window.lock();
+        if last.as_ref() == Some(&window) {
+            return;
+        }
+        *last = Some(window.clone());
+        drop(last);
+        let (title, x, y) = window;
+        self.commit_event(ScriptEvent::WindowAnchor { title, x, y });
+    }
+
+    /// Commit a numbered Label, so sections can be found again when editing
+    pub fn add_marker(&self) {
+        if !self.is_recording() {
+            return;
+        }
+        let number = self.markers.fetch_add(1, Ordering::SeqCst) + 1;
+        self.commit_event(ScriptEvent::Label {
+            name: format!("Marker {}", number),
+        });
+    }
+
+    /// Throttle live emission of mouse moves; everything else is always emitted
+    fn should_emit(&self, event: &ScriptEvent) -> bool {
+        if !matches!(event, ScriptEvent::MouseMove { .. }) {
+            return true;
+        }
+        let mut last_emit = self.last_move_emit.lock();
+        let now = self.clock.now();
+        if last_emit.is_some_and(|t| now.duration_since(t) < MOUSE_MOVE_EMIT_INTERVAL) {
+            return false;
+        }
+        *last_emit = Some(now);
+        true
     }
 
     // Helper to update position without adding event (not used with new logic but kept for safety)
@@ -94,6 +469,24 @@ impl RecordingState {
     pub fn get_mouse_position(&self) -> (f64, f64) {
         *self.mouse_position.lock()
     }
+
+    /// Whole-pixel movement since the last call, or None if there is nothing to record yet
+    /// The sub-pixel remainder carries over so throttled moves don't drift
+    pub fn relative_delta(&self, x: f64, y: f64) -> Option<(i32, i32)> {
+        let mut anchor = self.relative_anchor.lock();
+        let Some((ax, ay)) = *anchor else {
+            *anchor = Some((x, y));
+            return None;
+        };
+
+        let dx = (x - ax).round();
+        let dy = (y - ay).round();
+        if dx == 0.0 && dy == 0.0 {
+            return None;
+        }
+        *anchor = Some((ax + dx, ay + dy));
+        Some((dx as i32, dy as i32))
+    }
 }
 
 impl Default for RecordingState {
@@ -102,36 +495,333 @@ impl Default for RecordingState {
     }
 }
 
+/// Watch for a gap in input longer than `timeout_ms`, emitting `recording-idle` once per gap
+fn spawn_idle_watcher(session: u64, timeout_ms: u64) {
+    thread::spawn(move || {
+        let state = get_state();
+        let poll = Duration::from_millis(timeout_ms.clamp(1, 250));
+        while state.is_recording() && state.session.load(Ordering::SeqCst) == session {
+            if !state.idle.load(Ordering::SeqCst) && state.get_elapsed_ms() >= timeout_ms {
+                state.idle.store(true, Ordering::SeqCst);
+                crate::input_manager::emit_event("recording-idle", timeout_ms);
+            }
+            thread::sleep(poll);
+        }
+    });
+}
+
 /// Get the global recording state
 pub fn get_state() -> Arc<RecordingState> {
     Arc::clone(&RECORDING_STATE)
 }
 
 /// Start recording (flag only)
-pub fn start_recording() -> Result<(), String> {
+pub fn start_recording(options: RecordingOptions) -> Result<(), String> {
+    crate::settings::ensure_automation_enabled()?;
+    if options.ime_text {
+        return Err("IME text capture needs a frontend recording".to_string());
+    }
+    let state = get_state();
+
+    if state.is_recording() {
+        return Err("Already recording".to_string());
+    }
+
+    state.start(options);
+    Ok(())
+}
+
+/// Start a recording that only captures events sent by the frontend
+/// Used where the global hook can't capture input (e.g. Wayland)
+pub fn start_frontend_recording(options: RecordingOptions) -> Result<(), String> {
+    crate::settings::ensure_automation_enabled()?;
     let state = get_state();
 
     if state.is_recording() {
         return Err("Already recording".to_string());
     }
 
-    state.start();
+    state.start_frontend(options);
     Ok(())
 }
 
 /// Stop recording and return recorded events
+/// Safe to call when not recording: returns the last recording, which is kept until the next start
 pub fn stop_recording() -> Vec<ScriptEvent> {
     let state = get_state();
     state.stop();
     state.get_events()
 }
 
+/// Clear the current recording's events without stopping it
+/// Emits `recording-cleared` so the live view can reset
+pub fn clear_recording() -> Result<(), String> {
+    if !get_state().clear() {
+        return Err("Not recording".to_string());
+    }
+    crate::input_manager::emit_event("recording-cleared", ());
+    Ok(())
+}
+
 /// Check if currently recording
 pub fn is_recording() -> bool {
     get_state().is_recording()
 }
 
+/// Events of the last finished recording, e.g. one stopped via hotkey
+/// Kept until the next recording starts; empty while a recording is in progress
+pub fn get_last_recording() -> Vec<ScriptEvent> {
+    let state = get_state();
+    if state.is_recording() {
+        return Vec::new();
+    }
+    state.get_events()
+}
+
+/// Display scale factor captured when the last recording started
+pub fn recording_scale_factor() -> f64 {
+    get_state().scale_factor()
+}
+
 /// Get currently recorded events (for real-time display)
 pub fn get_recorded_events() -> Vec<ScriptEvent> {
     get_state().get_events()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock that only moves when told to
+    struct MockClock {
+        base: Instant,
+        offset_ms: AtomicU64,
+    }
+
+    impl MockClock {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                base: Instant::now(),
+                offset_ms: AtomicU64::new(0),
+            })
+        }
+
+        fn advance(&self, ms: u64) {
+            self.offset_ms.fetch_add(ms, Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.base + Duration::from_millis(self.offset_ms.load(Ordering::SeqCst))
+        }
+    }
+
+    fn key_press() -> ScriptEvent {
+        ScriptEvent::KeyPress {
+            key: KeyboardKey::Char('a'),
+            key_code: None,
+        }
+    }
+
+    fn recording(options: RecordingOptions) -> (RecordingState, Arc<MockClock>) {
+        let clock = MockClock::new();
+        let state = RecordingState::with_clock(clock.clone());
+        state.start(options);
+        (state, clock)
+    }
+
+    #[test]
+    fn test_frontend_recording_times_frontend_events() {
+        let clock = MockClock::new();
+        let state = RecordingState::with_clock(clock.clone());
+        state.start_frontend(RecordingOptions::default());
+        assert!(state.is_frontend_only());
+
+        clock.advance(40);
+        state.commit_event(key_press());
+        assert!(state.stop());
+        assert!(!state.is_frontend_only());
+        assert_eq!(
+            state.get_events(),
+            vec![ScriptEvent::Delay { duration_ms: 40 }, key_press()]
+        );
+    }
+
+    #[test]
+    fn test_commit_event_inserts_elapsed_delay() {
+        let (state, clock) = recording(RecordingOptions::default());
+
+        clock.advance(150);
+        state.commit_event(key_press());
+        state.commit_event(key_press());
+
+        assert_eq!(
+            state.get_events(),
+            vec![
+                ScriptEvent::Delay { duration_ms: 150 },
+                key_press(),
+                key_press()
+            ]
+        );
+        assert_eq!(state.get_elapsed_ms(), 0);
+    }
+
+    #[test]
+    fn test_clear_resets_events_and_timing() {
+        let (state, clock) = recording(RecordingOptions::default());
+        clock.advance(100);
+        state.commit_event(key_press());
+
+        clock.advance(300);
+        assert!(state.clear());
+        assert!(state.is_recording());
+        assert!(state.get_events().is_empty());
+
+        clock.advance(20);
+        state.commit_event(key_press());
+        assert_eq!(
+            state.get_events(),
+            vec![ScriptEvent::Delay { duration_ms: 20 }, key_press()]
+        );
+
+        assert!(state.stop());
+        assert!(!state.clear());
+    }
+
+    #[test]
+    fn test_window_anchor_only_on_change() {
+        let (state, _clock) = recording(RecordingOptions::default());
+        let editor = Some(("Editor".to_string(), 100.0, 50.0));
+        state.note_window(editor.clone());
+        state.note_window(editor);
+        state.note_window(None);
+        state.note_window(Some(("Editor".to_string(), 300.0, 50.0)));
+
+        assert_eq!(
+            state.get_events(),
+            vec![
+                ScriptEvent::WindowAnchor {
+                    title: "Editor".to_string(),
+                    x: 100.0,
+                    y: 50.0
+                },
+                ScriptEvent::WindowAnchor {
+                    title: "Editor".to_string(),
+                    x: 300.0,
+                    y: 50.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup_window_drops_bounced_presses() {
+        let (state, clock) = recording(RecordingOptions {
+            dedup_window_ms: Some(5),
+            ..Default::default()
+        });
+        assert!(!state.is_duplicate_key_press(&key_press()));
+        clock.advance(2);
+        assert!(state.is_duplicate_key_press(&key_press()));
+        // A release in between makes the next press a real one
+        state.note_key_release(&KeyboardKey::Char('a'));
+        assert!(!state.is_duplicate_key_press(&key_press()));
+        clock.advance(10);
+        assert!(!state.is_duplicate_key_press(&key_press()));
+    }
+
+    #[test]
+    fn test_composition_needs_ime_frontend_recording() {
+        let ime_options = || RecordingOptions {
+            ime_text: true,
+            ..Default::default()
+        };
+        let plain = RecordingState::with_clock(MockClock::new());
+        plain.start_frontend(RecordingOptions::default());
+        plain.commit_composition("日本".to_string());
+        assert!(plain.get_events().is_empty());
+
+        // The global hook records the composing keys itself
+        let (global, _clock) = recording(ime_options());
+        global.commit_composition("日本".to_string());
+        assert!(global.get_events().is_empty());
+
+        let ime = RecordingState::with_clock(MockClock::new());
+        ime.start_frontend(ime_options());
+        ime.commit_composition("日本".to_string());
+        assert_eq!(
+            ime.get_events(),
+            vec![ScriptEvent::TypeText {
+                text: "日本".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_mouse_hold_duration_is_kept() {
+        let (state, clock) = recording(RecordingOptions {
+            idle_timeout_ms: Some(100),
+            ..Default::default()
+        });
+        let press = ScriptEvent::MousePress {
+            button: crate::script::MouseButton::Left,
+            x: 10.0,
+            y: 10.0,
+        };
+        let release = ScriptEvent::MouseRelease {
+            button: crate::script::MouseButton::Left,
+            x: 10.0,
+            y: 10.0,
+        };
+
+        state.commit_event(press.clone());
+        // Held without any moves, long enough for the idle watcher to fire
+        clock.advance(350);
+        state.idle.store(true, Ordering::SeqCst);
+        state.commit_event(release.clone());
+
+        assert_eq!(
+            state.get_events(),
+            vec![press, ScriptEvent::Delay { duration_ms: 350 }, release]
+        );
+    }
+
+    #[test]
+    fn test_fixed_interval_overrides_clock() {
+        let (state, clock) = recording(RecordingOptions {
+            fixed_interval_ms: Some(50),
+            ..Default::default()
+        });
+
+        clock.advance(1000);
+        state.commit_event(key_press());
+
+        assert_eq!(
+            state.get_events(),
+            vec![ScriptEvent::Delay { duration_ms: 50 }, key_press()]
+        );
+    }
+
+    #[test]
+    fn test_commit_ignored_when_not_recording() {
+        let (state, _clock) = recording(RecordingOptions::default());
+        state.stop();
+
+        state.commit_event(key_press());
+        assert!(state.get_events().is_empty());
+    }
+
+    #[test]
+    fn test_mouse_move_emission_is_throttled() {
+        let (state, clock) = recording(RecordingOptions::default());
+        let mouse_move = ScriptEvent::MouseMove { x: 1.0, y: 1.0 };
+
+        assert!(state.should_emit(&mouse_move));
+        clock.advance(50);
+        assert!(!state.should_emit(&mouse_move));
+        assert!(state.should_emit(&key_press()));
+        clock.advance(50);
+        assert!(state.should_emit(&mouse_move));
+    }
+}
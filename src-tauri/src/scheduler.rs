@@ -0,0 +1,419 @@
+//! Scheduler module - runs scripts at scheduled times
+//! Jobs are persisted and checked by a background timer thread
+
+use crate::player;
+use crate::script::Script;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the timer thread checks for due jobs
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Global scheduler state
+static SCHEDULER_STATE: Lazy<Arc<SchedulerState>> = Lazy::new(|| Arc::new(SchedulerState::new()));
+
+/// A one-shot scheduled playback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    /// Unique identifier
+    pub id: String,
+    /// Script to play
+    pub script: Script,
+    /// When to play, in milliseconds since the Unix epoch
+    pub at_unix_ms: u64,
+}
+
+/// A playback that repeats on a fixed interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringJob {
+    /// Unique identifier
+    pub id: String,
+    /// Path to the script file to play
+    pub script_path: String,
+    /// Time between runs in milliseconds
+    pub interval_ms: u64,
+    /// Runs left before the job is removed (None = forever)
+    pub remaining_runs: Option<u32>,
+    /// Next run, in milliseconds since the Unix epoch
+    pub next_run_unix_ms: u64,
+    /// Whether the caller confirmed the script may loop forever
+    #[serde(default)]
+    pub confirm_infinite: bool,
+}
+
+/// On-disk format of the schedules file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleFile {
+    #[serde(default)]
+    jobs: Vec<ScheduledJob>,
+    #[serde(default)]
+    recurring: Vec<RecurringJob>,
+}
+
+/// Scheduler state manager
+pub struct SchedulerState {
+    /// Whether the timer thread is running
+    is_running: AtomicBool,
+    /// Pending one-shot jobs
+    jobs: RwLock<Vec<ScheduledJob>>,
+    /// Recurring jobs
+    recurring: RwLock<Vec<RecurringJob>>,
+    /// File the jobs are persisted to (None until storage is initialized)
+    storage_path: RwLock<Option<PathBuf>>,
+}
+
+impl SchedulerState {
+    pub fn new() -> Self {
+        Self {
+            is_running: AtomicBool::new(false),
+            jobs: RwLock::new(Vec::new()),
+            recurring: RwLock::new(Vec::new()),
+            storage_path: RwLock::new(None),
+        }
+    }
+
+    /// Load jobs from `path` and persist future changes there
+    pub fn init_storage(&self, path: PathBuf) -> Result<(), String> {
+        if path.exists() {
+            let content =
+                fs::read_to_string(&path).map_err(|e| format!("File read error: {}", e))?;
+            let file: ScheduleFile =
+                serde_json::from_str(&content).map_err(|e| format!("Parse error: {}", e))?;
+            *self.jobs.write() = file.jobs;
+            *self.recurring.write() = file.recurring;
+        }
+        *self.storage_path.write() = Some(path);
+        Ok(())
+    }
+
+    /// Write the jobs to the storage file, if one is configured
+    pub fn persist(&self) -> Result<(), String> {
+        let path = match self.storage_path.read().clone() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let file = ScheduleFile {
+            jobs: self.jobs.read().clone(),
+            recurring: self.recurring.read().clone(),
+        };
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Serialization error: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("File write error: {}", e))
+    }
+
+    pub fn add(&self, job: ScheduledJob) {
+        self.jobs.write().push(job);
+    }
+
+    pub fn add_recurring(&self, job: RecurringJob) {
+        self.recurring.write().push(job);
+    }
+
+    /// Remove a one-shot or recurring job by ID, returning whether it existed
+    pub fn remove(&self, id: &str) -> bool {
+        let mut jobs = self.jobs.write();
+        let mut recurring = self.recurring.write();
+        let before = jobs.len() + recurring.len();
+        jobs.retain(|j| j.id != id);
+        recurring.retain(|j| j.id != id);
+        jobs.len() + recurring.len() != before
+    }
+
+    pub fn list_recurring(&self) -> Vec<RecurringJob> {
+        self.recurring.read().clone()
+    }
+
+    /// Advance recurring jobs due at or before `now_ms`
+    /// Returns the job to fire, if any, and whether any job changed
+    /// At most one job fires per tick; the others stay due for the next tick.
+    /// While `playing`, due runs are skipped instead of fired
+    pub fn advance_recurring(&self, now_ms: u64, playing: bool) -> (Option<RecurringJob>, bool) {
+        let mut recurring = self.recurring.write();
+        let mut to_fire = None;
+        let mut changed = false;
+
+        for job in recurring
+            .iter_mut()
+            .filter(|j| j.next_run_unix_ms <= now_ms)
+        {
+            if to_fire.is_some() {
+                continue;
+            }
+            changed = true;
+            job.next_run_unix_ms = now_ms + job.interval_ms;
+            if playing {
+                continue;
+            }
+            if let Some(remaining) = job.remaining_runs.as_mut() {
+                *remaining = remaining.saturating_sub(1);
+            }
+            to_fire = Some(job.clone());
+        }
+
+        recurring.retain(|j| j.remaining_runs != Some(0));
+        (to_fire, changed)
+    }
+
+    pub fn list(&self) -> Vec<ScheduledJob> {
+        self.jobs.read().clone()
+    }
+
+    /// Remove and return all jobs due at or before `now_ms`
+    pub fn take_due(&self, now_ms: u64) -> Vec<ScheduledJob> {
+        let mut jobs = self.jobs.write();
+        let (due, pending) = jobs.drain(..).partition(|j| j.at_unix_ms <= now_ms);
+        *jobs = pending;
+        due
+    }
+}
+
+impl Default for SchedulerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the global scheduler state
+pub fn get_state() -> Arc<SchedulerState> {
+    Arc::clone(&SCHEDULER_STATE)
+}
+
+/// Current time in milliseconds since the Unix epoch
+pub fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Persist the jobs, logging failures
+fn persist_jobs() {
+    if let Err(e) = get_state().persist() {
+        log::error!("Failed to persist schedules: {}", e);
+    }
+}
+
+/// Generate a unique job ID
+fn job_id() -> String {
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("job_{}", duration.as_nanos())
+}
+
+/// Load persisted jobs and start the timer thread
+pub fn init(path: PathBuf) -> Result<(), String> {
+    let state = get_state();
+    let loaded = state.init_storage(path);
+
+    if state.is_running.swap(true, Ordering::SeqCst) {
+        return loaded;
+    }
+
+    thread::spawn(|| loop {
+        thread::sleep(TICK_INTERVAL);
+
+        let state = get_state();
+        let now_ms = now_unix_ms();
+        let due = state.take_due(now_ms);
+        let (recurring_job, recurring_changed) =
+            state.advance_recurring(now_ms, player::is_playing());
+        if due.is_empty() && !recurring_changed {
+            continue;
+        }
+        persist_jobs();
+
+        // One after another: a job due while another plays would be rejected as already playing
+        for job in due {
+            if let Err(e) = player::play_script_blocking(job.script) {
+                log::error!("Scheduled job {} failed: {}", job.id, e);
+            }
+        }
+
+        if let Some(job) = recurring_job {
+            // The file may have changed since scheduling, so check it again
+            let result = load_recurring_script(&job.script_path, Some(job.confirm_infinite))
+                .and_then(player::play_script);
+            if let Err(e) = result {
+                log::error!("Recurring job for {} failed: {}", job.script_path, e);
+            }
+        }
+    });
+
+    loaded
+}
+
+/// Schedule a script to play at `at_unix_ms`
+pub fn schedule_playback(script: Script, at_unix_ms: u64) -> ScheduledJob {
+    let job = ScheduledJob {
+        id: job_id(),
+        script,
+        at_unix_ms,
+    };
+    get_state().add(job.clone());
+    persist_jobs();
+    job
+}
+
+/// Load a recurring job's script, refusing a tampered file or an unconfirmed infinite loop
+fn load_recurring_script(path: &str, confirm_infinite: Option<bool>) -> Result<Script, String> {
+    let script = Script::load(path)?;
+    script.verify_checksum()?;
+    player::ensure_infinite_confirmed(&script.loop_config, confirm_infinite)?;
+    Ok(script)
+}
+
+/// Schedule a saved script to play every `interval_ms`, `count` times (None = forever)
+pub fn schedule_recurring(
+    script_path: String,
+    interval_ms: u64,
+    count: Option<u32>,
+    confirm_infinite: Option<bool>,
+) -> Result<RecurringJob, String> {
+    if interval_ms == 0 {
+        return Err("Interval must be greater than zero".to_string());
+    }
+    if count == Some(0) {
+        return Err("Count must be greater than zero".to_string());
+    }
+    load_recurring_script(&script_path, confirm_infinite)?;
+
+    let job = RecurringJob {
+        id: job_id(),
+        script_path,
+        interval_ms,
+        remaining_runs: count,
+        next_run_unix_ms: now_unix_ms() + interval_ms,
+        confirm_infinite: confirm_infinite.unwrap_or(false),
+    };
+    get_state().add_recurring(job.clone());
+    persist_jobs();
+    Ok(job)
+}
+
+/// List recurring jobs with their next run time
+pub fn list_recurring() -> Vec<RecurringJob> {
+    get_state().list_recurring()
+}
+
+/// List pending scheduled jobs
+pub fn list_scheduled() -> Vec<ScheduledJob> {
+    get_state().list()
+}
+
+/// Cancel a scheduled job by ID
+pub fn cancel_scheduled(id: &str) -> Result<(), String> {
+    if !get_state().remove(id) {
+        return Err(format!("Scheduled job not found: {}", id));
+    }
+    persist_jobs();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, at_unix_ms: u64) -> ScheduledJob {
+        ScheduledJob {
+            id: id.to_string(),
+            script: Script::default(),
+            at_unix_ms,
+        }
+    }
+
+    fn recurring(path: &str, next_run_unix_ms: u64, remaining_runs: Option<u32>) -> RecurringJob {
+        RecurringJob {
+            id: path.to_string(),
+            script_path: path.to_string(),
+            interval_ms: 1000,
+            remaining_runs,
+            next_run_unix_ms,
+            confirm_infinite: false,
+        }
+    }
+
+    fn fired(result: (Option<RecurringJob>, bool)) -> (Option<String>, bool) {
+        (result.0.map(|job| job.script_path), result.1)
+    }
+
+    #[test]
+    fn test_take_due_keeps_pending_jobs() {
+        let state = SchedulerState::new();
+        state.add(job("due", 100));
+        state.add(job("later", 300));
+
+        let due = state.take_due(200);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, "due");
+        assert_eq!(state.list().len(), 1);
+        assert_eq!(state.list()[0].id, "later");
+        assert!(state.take_due(200).is_empty());
+    }
+
+    #[test]
+    fn test_recurring_not_yet_due() {
+        let state = SchedulerState::new();
+        state.add_recurring(recurring("a.json", 500, None));
+
+        assert_eq!(fired(state.advance_recurring(400, false)), (None, false));
+        assert_eq!(state.list_recurring()[0].next_run_unix_ms, 500);
+    }
+
+    #[test]
+    fn test_recurring_removed_after_last_run() {
+        let state = SchedulerState::new();
+        state.add_recurring(recurring("a.json", 0, Some(2)));
+
+        assert_eq!(
+            fired(state.advance_recurring(0, false)),
+            (Some("a.json".to_string()), true)
+        );
+        assert_eq!(state.list_recurring()[0].remaining_runs, Some(1));
+        assert_eq!(state.list_recurring()[0].next_run_unix_ms, 1000);
+
+        assert_eq!(
+            fired(state.advance_recurring(1000, false)),
+            (Some("a.json".to_string()), true)
+        );
+        assert!(state.list_recurring().is_empty());
+    }
+
+    #[test]
+    fn test_recurring_skipped_while_playing() {
+        let state = SchedulerState::new();
+        state.add_recurring(recurring("a.json", 0, Some(1)));
+
+        assert_eq!(fired(state.advance_recurring(0, true)), (None, true));
+        let job = &state.list_recurring()[0];
+        assert_eq!(job.remaining_runs, Some(1));
+        assert_eq!(job.next_run_unix_ms, 1000);
+    }
+
+    #[test]
+    fn test_recurring_jobs_due_together_both_run() {
+        let state = SchedulerState::new();
+        state.add_recurring(recurring("a.json", 0, None));
+        state.add_recurring(recurring("b.json", 0, None));
+
+        assert_eq!(
+            fired(state.advance_recurring(100, false)),
+            (Some("a.json".to_string()), true)
+        );
+        // b stays due instead of being pushed back alongside a
+        assert_eq!(state.list_recurring()[1].next_run_unix_ms, 0);
+        assert_eq!(
+            fired(state.advance_recurring(600, false)),
+            (Some("b.json".to_string()), true)
+        );
+        assert_eq!(state.list_recurring()[0].next_run_unix_ms, 1100);
+        assert_eq!(state.list_recurring()[1].next_run_unix_ms, 1600);
+    }
+}
@@ -3,6 +3,16 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Next id handed out by `next_event_id`
+static NEXT_EVENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A fresh id for a script event, unique within this run
+pub fn next_event_id() -> u64 {
+    NEXT_EVENT_ID.fetch_add(1, Ordering::SeqCst)
+}
 
 /// Mouse button types
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -14,34 +24,195 @@ pub enum MouseButton {
     Back,
     Forward,
     Unknown,
+    /// Additional side buttons, by the platform's raw button number
+    Extra(u8),
 }
 
+/// rdev `Unknown(code)` values of the (back, forward) side buttons on this platform
+#[cfg(target_os = "windows")]
+const SIDE_BUTTON_CODES: (u8, u8) = (1, 2);
+#[cfg(target_os = "macos")]
+const SIDE_BUTTON_CODES: (u8, u8) = (3, 4);
+#[cfg(all(unix, not(target_os = "macos")))]
+const SIDE_BUTTON_CODES: (u8, u8) = (8, 9);
+
 impl From<rdev::Button> for MouseButton {
     fn from(btn: rdev::Button) -> Self {
+        let (back, forward) = SIDE_BUTTON_CODES;
         match btn {
             rdev::Button::Left => MouseButton::Left,
             rdev::Button::Right => MouseButton::Right,
             rdev::Button::Middle => MouseButton::Middle,
-            rdev::Button::Unknown(1) => MouseButton::Back,
-            rdev::Button::Unknown(2) => MouseButton::Forward,
-            _ => MouseButton::Unknown,
+            rdev::Button::Unknown(code) if code == back => MouseButton::Back,
+            rdev::Button::Unknown(code) if code == forward => MouseButton::Forward,
+            rdev::Button::Unknown(code) => MouseButton::Extra(code),
         }
     }
 }
 
-impl From<MouseButton> for enigo::Button {
-    fn from(btn: MouseButton) -> Self {
+impl TryFrom<MouseButton> for enigo::Button {
+    type Error = String;
+
+    /// enigo can only press the five standard buttons
+    fn try_from(btn: MouseButton) -> Result<Self, Self::Error> {
         match btn {
-            MouseButton::Left => enigo::Button::Left,
-            MouseButton::Right => enigo::Button::Right,
-            MouseButton::Middle => enigo::Button::Middle,
-            MouseButton::Back => enigo::Button::Back,
-            MouseButton::Forward => enigo::Button::Forward,
-            MouseButton::Unknown => enigo::Button::Left,
+            MouseButton::Left => Ok(enigo::Button::Left),
+            MouseButton::Right => Ok(enigo::Button::Right),
+            MouseButton::Middle => Ok(enigo::Button::Middle),
+            MouseButton::Back => Ok(enigo::Button::Back),
+            MouseButton::Forward => Ok(enigo::Button::Forward),
+            MouseButton::Unknown => Ok(enigo::Button::Left),
+            MouseButton::Extra(code) => Err(format!(
+                "Mouse button {} is not supported for playback",
+                code
+            )),
         }
     }
 }
 
+/// Unshifted character of a letter or digit key
+fn alphanumeric_char(key: rdev::Key) -> Option<char> {
+    use rdev::Key::*;
+    let c = match key {
+        KeyA => 'a',
+        KeyB => 'b',
+        KeyC => 'c',
+        KeyD => 'd',
+        KeyE => 'e',
+        KeyF => 'f',
+        KeyG => 'g',
+        KeyH => 'h',
+        KeyI => 'i',
+        KeyJ => 'j',
+        KeyK => 'k',
+        KeyL => 'l',
+        KeyM => 'm',
+        KeyN => 'n',
+        KeyO => 'o',
+        KeyP => 'p',
+        KeyQ => 'q',
+        KeyR => 'r',
+        KeyS => 's',
+        KeyT => 't',
+        KeyU => 'u',
+        KeyV => 'v',
+        KeyW => 'w',
+        KeyX => 'x',
+        KeyY => 'y',
+        KeyZ => 'z',
+        Num0 => '0',
+        Num1 => '1',
+        Num2 => '2',
+        Num3 => '3',
+        Num4 => '4',
+        Num5 => '5',
+        Num6 => '6',
+        Num7 => '7',
+        Num8 => '8',
+        Num9 => '9',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Virtual-key code for a letter or digit (VK_A..VK_Z, VK_0..VK_9)
+#[cfg(target_os = "windows")]
+fn native_key_code(c: char) -> Option<u32> {
+    Some(c.to_ascii_uppercase() as u32)
+}
+
+/// X11 keysym for a letter or digit (same as its ASCII value)
+#[cfg(all(unix, not(target_os = "macos")))]
+fn native_key_code(c: char) -> Option<u32> {
+    Some(c as u32)
+}
+
+/// macOS virtual keycode (kVK_ANSI_*) for a letter or digit
+#[cfg(target_os = "macos")]
+fn native_key_code(c: char) -> Option<u32> {
+    const ANSI: &[(char, u32)] = &[
+        ('a', 0x00),
+        ('s', 0x01),
+        ('d', 0x02),
+        ('f', 0x03),
+        ('h', 0x04),
+        ('g', 0x05),
+        ('z', 0x06),
+        ('x', 0x07),
+        ('c', 0x08),
+        ('v', 0x09),
+        ('b', 0x0B),
+        ('q', 0x0C),
+        ('w', 0x0D),
+        ('e', 0x0E),
+        ('r', 0x0F),
+        ('y', 0x10),
+        ('t', 0x11),
+        ('1', 0x12),
+        ('2', 0x13),
+        ('3', 0x14),
+        ('4', 0x15),
+        ('6', 0x16),
+        ('5', 0x17),
+        ('9', 0x19),
+        ('7', 0x1A),
+        ('8', 0x1C),
+        ('0', 0x1D),
+        ('o', 0x1F),
+        ('u', 0x20),
+        ('i', 0x22),
+        ('p', 0x23),
+        ('l', 0x25),
+        ('j', 0x26),
+        ('k', 0x28),
+        ('n', 0x2D),
+        ('m', 0x2E),
+    ];
+    ANSI.iter().find(|(ch, _)| *ch == c).map(|(_, code)| *code)
+}
+
+/// Native code of a physical letter/digit key, usable with enigo `Key::Other`
+pub fn physical_key_code(key: rdev::Key) -> Option<u32> {
+    alphanumeric_char(key).and_then(native_key_code)
+}
+
+/// Keys rdev reports as `Unknown(code)` (context menu, media keys)
+/// Each entry is (special key name, rdev code, enigo `Key::Other` code) for this platform
+#[cfg(target_os = "windows")]
+pub const EXTRA_KEYS: &[(&str, u32, u32)] = &[
+    ("Apps", 0x5D, 0x5D),
+    ("VolumeMute", 0xAD, 0xAD),
+    ("VolumeDown", 0xAE, 0xAE),
+    ("VolumeUp", 0xAF, 0xAF),
+    ("MediaNextTrack", 0xB0, 0xB0),
+    ("MediaPrevTrack", 0xB1, 0xB1),
+    ("MediaStop", 0xB2, 0xB2),
+    ("MediaPlayPause", 0xB3, 0xB3),
+];
+
+/// Keys rdev reports as `Unknown(code)` (context menu, media keys)
+/// Each entry is (special key name, X11 keycode, X11 keysym) for this platform
+#[cfg(all(unix, not(target_os = "macos")))]
+pub const EXTRA_KEYS: &[(&str, u32, u32)] = &[
+    ("Apps", 135, 0xFF67),
+    ("VolumeMute", 121, 0x1008FF12),
+    ("VolumeDown", 122, 0x1008FF11),
+    ("VolumeUp", 123, 0x1008FF13),
+    ("MediaNextTrack", 171, 0x1008FF17),
+    ("MediaPlayPause", 172, 0x1008FF14),
+    ("MediaPrevTrack", 173, 0x1008FF16),
+    ("MediaStop", 174, 0x1008FF15),
+];
+
+/// Keys rdev reports as `Unknown(code)` (volume keys)
+/// Each entry is (special key name, rdev code, macOS virtual keycode)
+#[cfg(target_os = "macos")]
+pub const EXTRA_KEYS: &[(&str, u32, u32)] = &[
+    ("VolumeUp", 72, 72),
+    ("VolumeDown", 73, 73),
+    ("VolumeMute", 74, 74),
+];
+
 /// Keyboard key representation
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(tag = "type", content = "value")]
@@ -52,6 +223,70 @@ pub enum KeyboardKey {
     Special(String),
 }
 
+impl KeyboardKey {
+    /// Whether this is a modifier key (Shift, Control, Alt, Meta)
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self,
+            KeyboardKey::Special(name) if matches!(
+                name.as_str(),
+                "ShiftLeft"
+                    | "ShiftRight"
+                    | "ControlLeft"
+                    | "ControlRight"
+                    | "Alt"
+                    | "AltGr"
+                    | "MetaLeft"
+                    | "MetaRight"
+            )
+        )
+    }
+}
+
+impl KeyboardKey {
+    /// Name for showing to users, e.g. "Left Ctrl" or "A"
+    /// Not parseable back; use the raw name for that
+    pub fn display_name(&self) -> String {
+        match self {
+            KeyboardKey::Char(' ') => "Space".to_string(),
+            KeyboardKey::Char(c) => c.to_uppercase().to_string(),
+            KeyboardKey::Special(name) => match name.as_str() {
+                "ControlLeft" => "Left Ctrl",
+                "ControlRight" => "Right Ctrl",
+                "ShiftLeft" => "Left Shift",
+                "ShiftRight" => "Right Shift",
+                "MetaLeft" => "Left Win",
+                "MetaRight" => "Right Win",
+                "Return" => "Enter",
+                "Escape" => "Esc",
+                "UpArrow" => "Up",
+                "DownArrow" => "Down",
+                "LeftArrow" => "Left",
+                "RightArrow" => "Right",
+                "PageUp" => "Page Up",
+                "PageDown" => "Page Down",
+                other => other,
+            }
+            .to_string(),
+        }
+    }
+}
+
+/// Parse a key name as typed in the task editor: one character or a special key name
+impl std::str::FromStr for KeyboardKey {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => Err("Key name is empty".to_string()),
+            (Some(c), None) => Ok(KeyboardKey::Char(c)),
+            _ => Ok(KeyboardKey::Special(value.to_string())),
+        }
+    }
+}
+
 impl From<rdev::Key> for KeyboardKey {
     fn from(key: rdev::Key) -> Self {
         match key {
@@ -131,29 +366,100 @@ impl From<rdev::Key> for KeyboardKey {
             rdev::Key::KeyX => KeyboardKey::Char('x'),
             rdev::Key::KeyY => KeyboardKey::Char('y'),
             rdev::Key::KeyZ => KeyboardKey::Char('z'),
+            rdev::Key::Unknown(code) => EXTRA_KEYS
+                .iter()
+                .find(|(_, rdev_code, _)| *rdev_code == code)
+                .map(|(name, _, _)| KeyboardKey::Special(name.to_string()))
+                .unwrap_or_else(|| KeyboardKey::Special("Unknown".to_string())),
             _ => KeyboardKey::Special("Unknown".to_string()),
         }
     }
 }
 
 /// A single input event (keyboard or mouse)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "event_type")]
 pub enum ScriptEvent {
     /// Independent delay event / wait node
     Delay { duration_ms: u64 },
     /// Key press event
-    KeyPress { key: KeyboardKey },
+    KeyPress {
+        key: KeyboardKey,
+        /// Native code of the physical key that produced a character, if known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key_code: Option<u32>,
+    },
     /// Key release event
-    KeyRelease { key: KeyboardKey },
+    KeyRelease {
+        key: KeyboardKey,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key_code: Option<u32>,
+    },
     /// Mouse button press
     MousePress { button: MouseButton, x: f64, y: f64 },
     /// Mouse button release
     MouseRelease { button: MouseButton, x: f64, y: f64 },
     /// Mouse movement
     MouseMove { x: f64, y: f64 },
-    /// Mouse scroll
-    MouseScroll { delta_x: i64, delta_y: i64 },
+    /// Relative mouse movement, for apps that read raw deltas (e.g. game cameras)
+    MouseMoveRel { dx: i32, dy: i32 },
+    /// Mouse scroll, at the cursor position it was recorded at (if known)
+    MouseScroll {
+        delta_x: i64,
+        delta_y: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        x: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        y: Option<f64>,
+    },
+    /// Conditional branch on a screen pixel color
+    /// Jumps to `then_jump` if the pixel matches `rgb` within `tolerance`, else to `else_jump`
+    IfPixel {
+        x: f64,
+        y: f64,
+        rgb: [u8; 3],
+        tolerance: u8,
+        then_jump: usize,
+        else_jump: usize,
+    },
+    /// Wait until a screen pixel matches `rgb` within `tolerance`, for at most `max_wait_ms`
+    /// Never fails: playback continues once the pixel matches or the time is up
+    WaitForPixelOptional {
+        x: f64,
+        y: f64,
+        rgb: [u8; 3],
+        tolerance: u8,
+        max_wait_ms: u64,
+    },
+    /// Unconditional jump to another event index
+    Jump { target: usize },
+    /// Press and release a key `count` times, `interval_ms` apart
+    KeyRepeat {
+        key: KeyboardKey,
+        count: u32,
+        interval_ms: u64,
+    },
+    /// Run another task's events in place of this event
+    RunTask { id: String },
+    /// An event temporarily switched off in the editor; skipped during playback
+    Disabled { event: Box<ScriptEvent> },
+    /// A named marker for finding sections of a script; does nothing during playback
+    Label { name: String },
+    /// Read the clipboard text into the variable `var` for the rest of the run
+    CaptureClipboard { var: String },
+    /// Type the text previously captured into `var`
+    TypeVar { var: String },
+    /// Type text as committed by an IME, without replaying the keys that composed it
+    TypeText { text: String },
+    /// Mouse coordinates after this event belong to the window `title`, whose top-left corner
+    /// was at (`x`, `y`) when recorded; playback shifts them by how far the window has moved
+    /// Only the foreground window is checked, otherwise coordinates are used as recorded
+    WindowAnchor { title: String, x: f64, y: f64 },
+    /// Hold `modifiers` in order, tap `key`, then release the modifiers in reverse
+    Chord {
+        modifiers: Vec<KeyboardKey>,
+        key: KeyboardKey,
+    },
 }
 
 /// A task definition - trigger + action
@@ -171,12 +477,67 @@ pub struct Task {
     pub stop_key: Option<KeyboardKey>,
     /// Path to the script file to execute
     pub script_path: String,
+    /// Inline events to execute instead of loading `script_path`
+    #[serde(default)]
+    pub events: Option<Vec<ScriptEvent>>,
     /// Whether the task is enabled
     pub enabled: bool,
     /// Loop configuration
     pub loop_config: LoopConfig,
     /// Speed multiplier
     pub speed_multiplier: f64,
+    /// Only fire while NumLock/CapsLock are in this state
+    #[serde(default)]
+    pub requires_lock_state: Option<LockState>,
+}
+
+/// Required lock key states for a task trigger; `None` means either state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockState {
+    #[serde(default)]
+    pub num_lock: Option<bool>,
+    #[serde(default)]
+    pub caps_lock: Option<bool>,
+}
+
+/// A screen rectangle in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Smallest rectangle containing both
+    pub fn union(&self, other: &Rect) -> Rect {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x + self.width as i32).max(other.x + other.width as i32);
+        let bottom = (self.y + self.height as i32).max(other.y + other.height as i32);
+        Rect {
+            x: left,
+            y: top,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        }
+    }
+
+    /// Nearest point inside the rectangle
+    pub fn clamp(&self, x: i32, y: i32) -> (i32, i32) {
+        let right = self.x + (self.width as i32 - 1).max(0);
+        let bottom = self.y + (self.height as i32 - 1).max(0);
+        (x.clamp(self.x, right), y.clamp(self.y, bottom))
+    }
+}
+
+impl LockState {
+    /// Whether the current lock key states satisfy this requirement
+    pub fn matches(&self, num_lock: bool, caps_lock: bool) -> bool {
+        self.num_lock.is_none_or(|on| on == num_lock)
+            && self.caps_lock.is_none_or(|on| on == caps_lock)
+    }
 }
 
 /// Loop configuration for script execution
@@ -214,6 +575,56 @@ pub struct Script {
     pub loop_config: LoopConfig,
     /// Speed multiplier (1.0 = normal, 2.0 = double speed)
     pub speed_multiplier: f64,
+    /// Number of leading events over which speed ramps from 1x up to `speed_multiplier`
+    #[serde(default)]
+    pub ramp_events: u32,
+    /// Extra pause after every input event, independent of recorded delays
+    #[serde(default)]
+    pub global_event_padding_ms: u64,
+    /// Pause between moving to a recorded position and pressing the button
+    #[serde(default = "default_click_settle_ms")]
+    pub click_settle_ms: u64,
+    /// Replay characters via their recorded physical key code instead of unicode input
+    /// Helps games that ignore synthetic unicode; only correct on the recording's layout
+    #[serde(default)]
+    pub physical_keys: bool,
+    /// Type symbols and non-ASCII characters as text, letting the OS compose them
+    /// Fixes characters that need AltGr or dead keys on the current layout
+    #[serde(default)]
+    pub symbols_as_text: bool,
+    /// Whether disabled Delay events still wait during playback
+    #[serde(default)]
+    pub honor_disabled_delays: bool,
+    /// Move the cursor back to where it was when playback started
+    #[serde(default)]
+    pub restore_cursor: bool,
+    /// Schedule delays against a per-loop clock instead of sleeping each one independently
+    /// Stops sleep overshoot from accumulating over long scripts
+    #[serde(default)]
+    pub absolute_timing: bool,
+    /// Pull coordinates outside the current screens back onto the nearest edge
+    /// Guards scripts recorded on a larger setup; logs every clamp
+    #[serde(default)]
+    pub clamp_to_screen: bool,
+    /// Abort playback if the window of the last WindowAnchor leaves the foreground for good
+    /// Guards long unattended runs against clicking into whatever replaced a crashed app
+    #[serde(default)]
+    pub watch_target_window: bool,
+    /// Display scale factor of the monitor the script was recorded on
+    /// None when unknown (older files, scripts built from events); coordinates are then used as-is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale_factor: Option<f64>,
+    /// Hash of the events, written on save and checked on load (absent in older files)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// Stable id of each event, parallel to `events`, so the editor can track events across edits
+    /// Older files have none; `assign_event_ids` fills in the missing ones
+    #[serde(default)]
+    pub event_ids: Vec<u64>,
+}
+
+fn default_click_settle_ms() -> u64 {
+    10
 }
 
 impl Default for Script {
@@ -226,8 +637,334 @@ impl Default for Script {
             events: Vec::new(),
             loop_config: LoopConfig::default(),
             speed_multiplier: 1.0,
+            ramp_events: 0,
+            global_event_padding_ms: 0,
+            click_settle_ms: default_click_settle_ms(),
+            physical_keys: false,
+            symbols_as_text: false,
+            honor_disabled_delays: false,
+            restore_cursor: false,
+            absolute_timing: false,
+            clamp_to_screen: false,
+            watch_target_window: false,
+            scale_factor: None,
+            checksum: None,
+            event_ids: Vec::new(),
+        }
+    }
+}
+
+impl ScriptEvent {
+    /// Whether this event sends input (as opposed to waiting or control flow)
+    pub fn is_input(&self) -> bool {
+        !matches!(
+            self,
+            ScriptEvent::Delay { .. }
+                | ScriptEvent::IfPixel { .. }
+                | ScriptEvent::WaitForPixelOptional { .. }
+                | ScriptEvent::Jump { .. }
+                | ScriptEvent::RunTask { .. }
+                | ScriptEvent::Disabled { .. }
+                | ScriptEvent::Label { .. }
+                | ScriptEvent::CaptureClipboard { .. }
+                | ScriptEvent::WindowAnchor { .. }
+        )
+    }
+}
+
+/// An event that could not be deserialized during a lenient load
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedEvent {
+    /// Position of the event in the file
+    pub index: usize,
+    pub error: String,
+}
+
+/// Result of a lenient load: the recoverable script plus what was dropped
+#[derive(Debug, Clone, Serialize)]
+pub struct LenientScript {
+    pub script: Script,
+    pub skipped: Vec<SkippedEvent>,
+}
+
+/// 64-bit FNV-1a hash
+/// Catches partial writes and sync corruption; not meant to resist deliberate tampering
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Script {
+    /// Hash of the serialized events, as stored in `checksum`
+    pub fn compute_checksum(&self) -> Result<String, String> {
+        let json = serde_json::to_string(&self.events)
+            .map_err(|e| format!("Serialization error: {}", e))?;
+        Ok(format!("{:016x}", fnv1a(json.as_bytes())))
+    }
+
+    /// Error if the stored checksum doesn't match the events
+    /// Scripts saved without a checksum always pass
+    pub fn verify_checksum(&self) -> Result<(), String> {
+        let expected = match &self.checksum {
+            Some(checksum) => checksum,
+            None => return Ok(()),
+        };
+        let actual = self.compute_checksum()?;
+        if *expected != actual {
+            return Err(format!(
+                "Integrity error: checksum mismatch (expected {}, found {})",
+                expected, actual
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read and parse a script file
+    pub fn load(path: &str) -> Result<Script, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("File read error: {}", e))?;
+        let mut script: Script =
+            serde_json::from_str(&content).map_err(|e| format!("Parse error: {}", e))?;
+        script.assign_event_ids();
+        Ok(script)
+    }
+
+    /// Make `event_ids` line up with `events`, giving fresh ids to events without one
+    /// Ids loaded from a file are reserved so new ones never collide with them
+    pub fn assign_event_ids(&mut self) {
+        self.event_ids.truncate(self.events.len());
+        if let Some(max) = self.event_ids.iter().max() {
+            NEXT_EVENT_ID.fetch_max(max + 1, Ordering::SeqCst);
+        }
+        while self.event_ids.len() < self.events.len() {
+            self.event_ids.push(next_event_id());
+        }
+    }
+
+    /// Replace the events with the result of `edit`, carrying ids over to the events it kept
+    /// Edits that keep the event count keep ids by position; otherwise old and new events
+    /// are aligned like `diff_events`, and only unmatched events get fresh ids
+    pub fn edit_events(&mut self, edit: impl FnOnce(Vec<ScriptEvent>) -> Vec<ScriptEvent>) {
+        self.assign_event_ids();
+        let old_ids = std::mem::take(&mut self.event_ids);
+        let old_events = std::mem::take(&mut self.events);
+        let new_events = edit(old_events.clone());
+
+        self.event_ids = if new_events.len() == old_events.len() {
+            old_ids
+        } else {
+            carry_ids(&old_events, &old_ids, &new_events)
+        };
+        self.events = new_events;
+        self.assign_event_ids();
+    }
+
+    /// Remove the event at `index` along with its id
+    pub fn remove_event(&mut self, index: usize) -> Option<ScriptEvent> {
+        self.assign_event_ids();
+        if index >= self.events.len() {
+            return None;
+        }
+        self.event_ids.remove(index);
+        Some(self.events.remove(index))
+    }
+
+    /// Insert an event at `index` (clamped to the end), returning its new id
+    pub fn insert_event(&mut self, index: usize, event: ScriptEvent) -> u64 {
+        self.assign_event_ids();
+        let index = index.min(self.events.len());
+        let id = next_event_id();
+        self.events.insert(index, event);
+        self.event_ids.insert(index, id);
+        id
+    }
+
+    /// Move the event at `from` to `to` (clamped to the end), keeping its id
+    pub fn move_event(&mut self, from: usize, to: usize) {
+        self.assign_event_ids();
+        if from >= self.events.len() {
+            return;
+        }
+        let event = self.events.remove(from);
+        let id = self.event_ids.remove(from);
+        let to = to.min(self.events.len());
+        self.events.insert(to, event);
+        self.event_ids.insert(to, id);
+    }
+
+    /// Read a script file, skipping events that fail to parse instead of failing the whole file
+    pub fn load_lenient(path: &str) -> Result<LenientScript, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("File read error: {}", e))?;
+        Self::parse_lenient(&content)
+    }
+
+    /// Parse script JSON, deserializing each event on its own
+    pub fn parse_lenient(content: &str) -> Result<LenientScript, String> {
+        let mut value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("Parse error: {}", e))?;
+
+        // Pull the events out so the rest of the script can be parsed strictly
+        let raw_events = match value.get_mut("events") {
+            Some(serde_json::Value::Array(events)) => std::mem::take(events),
+            _ => Vec::new(),
+        };
+        let mut script: Script =
+            serde_json::from_value(value).map_err(|e| format!("Parse error: {}", e))?;
+
+        // Ids only line up with the raw events, so drop them if the counts differ
+        let ids = std::mem::take(&mut script.event_ids);
+        let keep_ids = ids.len() == raw_events.len();
+
+        let mut skipped = Vec::new();
+        for (index, raw) in raw_events.into_iter().enumerate() {
+            match serde_json::from_value::<ScriptEvent>(raw) {
+                Ok(event) => {
+                    script.events.push(event);
+                    if keep_ids {
+                        script.event_ids.push(ids[index]);
+                    }
+                }
+                Err(e) => skipped.push(SkippedEvent {
+                    index,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        script.assign_event_ids();
+
+        Ok(LenientScript { script, skipped })
+    }
+
+    /// Split into several scripts according to `mode`
+    /// Pieces keep this script's settings, get numbered (or label) names and drop leading
+    /// delays; jump targets are rebased onto each piece
+    pub fn split(&self, mode: SplitMode) -> Vec<Script> {
+        // Each piece's range, plus the label that opened it
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        let mut label: Option<&str> = None;
+        for (index, event) in self.events.iter().enumerate() {
+            match (mode, event) {
+                (SplitMode::AtGap { threshold_ms }, ScriptEvent::Delay { duration_ms })
+                    if *duration_ms > threshold_ms => {}
+                (SplitMode::AtLabels, ScriptEvent::Label { .. }) => {}
+                _ => continue,
+            }
+            ranges.push((start..index, label));
+            start = index + 1;
+            label = match event {
+                ScriptEvent::Label { name } => Some(name),
+                _ => None,
+            };
+        }
+        ranges.push((start..self.events.len(), label));
+
+        let mut pieces: Vec<Script> = Vec::new();
+        for (range, label) in ranges {
+            let leading = self.events[range.clone()]
+                .iter()
+                .take_while(|e| matches!(e, ScriptEvent::Delay { .. }))
+                .count();
+            let first = range.start + leading;
+            if first >= range.end {
+                continue;
+            }
+
+            let len = range.end - first;
+            let rebase = |target: usize| {
+                if (first..range.end).contains(&target) {
+                    target - first
+                } else {
+                    len
+                }
+            };
+            let events = self.events[first..range.end]
+                .iter()
+                .cloned()
+                .map(|mut event| {
+                    match &mut event {
+                        ScriptEvent::IfPixel {
+                            then_jump,
+                            else_jump,
+                            ..
+                        } => {
+                            *then_jump = rebase(*then_jump);
+                            *else_jump = rebase(*else_jump);
+                        }
+                        ScriptEvent::Jump { target } => *target = rebase(*target),
+                        _ => {}
+                    }
+                    event
+                })
+                .collect();
+
+            let mut piece = Script {
+                name: match label {
+                    Some(label) => format!("{} - {}", self.name, label),
+                    None => format!("{} ({})", self.name, pieces.len() + 1),
+                },
+                events,
+                event_ids: self
+                    .event_ids
+                    .get(first..range.end)
+                    .map(<[u64]>::to_vec)
+                    .unwrap_or_default(),
+                // Pieces hold different events, so the parent's checksum doesn't apply
+                checksum: None,
+                ..self.clone()
+            };
+            piece.assign_event_ids();
+            pieces.push(piece);
+        }
+        pieces
+    }
+}
+
+/// Ids for `new_events`, reusing the id of each old event it aligns with
+fn carry_ids(old_events: &[ScriptEvent], old_ids: &[u64], new_events: &[ScriptEvent]) -> Vec<u64> {
+    let diffs = match crate::diff::diff_events(old_events, new_events) {
+        Ok(diffs) => diffs,
+        Err(e) => {
+            log::warn!("Giving edited events fresh ids: {}", e);
+            return new_events.iter().map(|_| next_event_id()).collect();
+        }
+    };
+    let mut deleted = vec![false; old_events.len()];
+    let mut inserted = vec![false; new_events.len()];
+    for diff in diffs {
+        match diff {
+            crate::diff::ScriptDiff::Deleted { index_a, .. } => deleted[index_a] = true,
+            crate::diff::ScriptDiff::Inserted { index_b, .. } => inserted[index_b] = true,
+            crate::diff::ScriptDiff::DelayChanged { .. } => {}
         }
     }
+
+    // Aligned events pair up in order
+    let mut kept = old_ids
+        .iter()
+        .zip(deleted)
+        .filter(|(_, deleted)| !deleted)
+        .map(|(id, _)| *id);
+    inserted
+        .into_iter()
+        .map(|inserted| {
+            if inserted {
+                next_event_id()
+            } else {
+                kept.next().unwrap_or_else(next_event_id)
+            }
+        })
+        .collect()
+}
+
+/// Where `Script::split` cuts a script
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+pub enum SplitMode {
+    /// Cut at every delay longer than `threshold_ms`, dropping that delay
+    AtGap { threshold_ms: u64 },
+    /// Cut at every Label event; each piece is named after the label that opens it
+    AtLabels,
 }
 
 #[allow(dead_code)]
@@ -246,6 +983,73 @@ impl Default for AppConfig {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_keyboard_key() {
+        assert_eq!("a".parse::<KeyboardKey>(), Ok(KeyboardKey::Char('a')));
+        assert_eq!(
+            " F9 ".parse::<KeyboardKey>(),
+            Ok(KeyboardKey::Special("F9".to_string()))
+        );
+        assert!("  ".parse::<KeyboardKey>().is_err());
+    }
+
+    #[test]
+    fn test_key_display_name() {
+        assert_eq!(KeyboardKey::Char('q').display_name(), "Q");
+        assert_eq!(
+            KeyboardKey::Special("ControlLeft".to_string()).display_name(),
+            "Left Ctrl"
+        );
+        assert_eq!(KeyboardKey::Special("F9".to_string()).display_name(), "F9");
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_lock_state_matches() {
+        let numpad = LockState {
+            num_lock: Some(true),
+            caps_lock: None,
+        };
+        assert!(numpad.matches(true, false));
+        assert!(numpad.matches(true, true));
+        assert!(!numpad.matches(false, false));
+        assert!(LockState::default().matches(false, true));
+    }
+
+    #[test]
+    fn test_rect_union_and_clamp() {
+        let left = Rect {
+            x: -1920,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let main = Rect {
+            x: 0,
+            y: 0,
+            width: 2560,
+            height: 1440,
+        };
+        let desktop = left.union(&main);
+        assert_eq!(
+            desktop,
+            Rect {
+                x: -1920,
+                y: 0,
+                width: 4480,
+                height: 1440,
+            }
+        );
+        assert_eq!(desktop.clamp(-3000, 500), (-1920, 500));
+        assert_eq!(desktop.clamp(2600, 1500), (2559, 1439));
+        assert_eq!(desktop.clamp(100, 100), (100, 100));
+    }
+
     #[test]
     fn test_script_creation() {
         let script = Script {
@@ -266,4 +1070,150 @@ mod tests {
         let parsed: Script = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.name, "Test");
     }
+
+    #[test]
+    fn test_event_ids_follow_edits() {
+        let mut script = Script {
+            events: vec![
+                ScriptEvent::Delay { duration_ms: 1 },
+                ScriptEvent::Delay { duration_ms: 2 },
+                ScriptEvent::Delay { duration_ms: 3 },
+            ],
+            ..Default::default()
+        };
+        script.assign_event_ids();
+        let ids = script.event_ids.clone();
+        assert_eq!(ids.len(), 3);
+
+        script.remove_event(0);
+        assert_eq!(script.event_ids, vec![ids[1], ids[2]]);
+
+        script.move_event(1, 0);
+        assert_eq!(script.event_ids, vec![ids[2], ids[1]]);
+        assert_eq!(script.events[0], ScriptEvent::Delay { duration_ms: 3 });
+
+        let id = script.insert_event(9, ScriptEvent::Delay { duration_ms: 4 });
+        assert_eq!(script.event_ids, vec![ids[2], ids[1], id]);
+        assert!(!ids.contains(&id));
+    }
+
+    #[test]
+    fn test_edit_events_keeps_ids_of_surviving_events() {
+        let press = |c| ScriptEvent::KeyPress {
+            key: KeyboardKey::Char(c),
+            key_code: None,
+        };
+        let mut script = Script {
+            events: vec![press('a'), press('b'), press('c'), press('d')],
+            ..Default::default()
+        };
+        script.assign_event_ids();
+        let ids = script.event_ids.clone();
+
+        // Dropping an event from the middle must not shift ids onto its neighbours
+        script.edit_events(|events| events.into_iter().filter(|e| *e != press('b')).collect());
+        assert_eq!(script.event_ids, vec![ids[0], ids[2], ids[3]]);
+
+        script.edit_events(|mut events| {
+            events.insert(1, press('x'));
+            events
+        });
+        assert_eq!(script.event_ids[0], ids[0]);
+        assert!(!ids.contains(&script.event_ids[1]));
+        assert_eq!(script.event_ids[2..], [ids[2], ids[3]]);
+    }
+
+    #[test]
+    fn test_split_at_gap() {
+        let script = Script {
+            name: "Long".to_string(),
+            events: vec![
+                ScriptEvent::Delay { duration_ms: 500 },
+                ScriptEvent::Jump { target: 2 },
+                ScriptEvent::Delay { duration_ms: 100 },
+                ScriptEvent::Delay { duration_ms: 5000 },
+                ScriptEvent::Jump { target: 5 },
+                ScriptEvent::Delay { duration_ms: 10 },
+            ],
+            checksum: Some("parent".to_string()),
+            ..Default::default()
+        };
+
+        let pieces = script.split(SplitMode::AtGap { threshold_ms: 1000 });
+        assert_eq!(pieces.len(), 2);
+        assert!(pieces.iter().all(|piece| piece.checksum.is_none()));
+        assert_eq!(pieces[0].name, "Long (1)");
+        assert_eq!(
+            pieces[0].events,
+            vec![
+                ScriptEvent::Jump { target: 1 },
+                ScriptEvent::Delay { duration_ms: 100 },
+            ]
+        );
+        assert_eq!(
+            pieces[1].events,
+            vec![
+                ScriptEvent::Jump { target: 1 },
+                ScriptEvent::Delay { duration_ms: 10 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_at_labels() {
+        let label = |name: &str| ScriptEvent::Label {
+            name: name.to_string(),
+        };
+        let script = Script {
+            name: "Long".to_string(),
+            events: vec![
+                ScriptEvent::Delay { duration_ms: 1 },
+                label("Login"),
+                ScriptEvent::Delay { duration_ms: 200 },
+                ScriptEvent::Jump { target: 4 },
+                ScriptEvent::Delay { duration_ms: 2 },
+                label("Export"),
+                ScriptEvent::Delay { duration_ms: 3 },
+                ScriptEvent::Jump { target: 0 },
+            ],
+            ..Default::default()
+        };
+
+        let pieces = script.split(SplitMode::AtLabels);
+        // Nothing but a delay before the first label, so no unnamed piece
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].name, "Long - Login");
+        assert_eq!(
+            pieces[0].events,
+            vec![
+                ScriptEvent::Jump { target: 1 },
+                ScriptEvent::Delay { duration_ms: 2 },
+            ]
+        );
+        assert_eq!(pieces[1].name, "Long - Export");
+        // Jumps out of the piece point past its end
+        assert_eq!(pieces[1].events, vec![ScriptEvent::Jump { target: 1 }]);
+    }
+
+    #[test]
+    fn test_lenient_load_skips_unknown_events() {
+        let json = r#"{
+            "name": "Test",
+            "description": "",
+            "created_at": "2024-01-01T00:00:00Z",
+            "modified_at": "2024-01-01T00:00:00Z",
+            "events": [
+                { "event_type": "Delay", "duration_ms": 10 },
+                { "event_type": "FromTheFuture" },
+                { "event_type": "Delay", "duration_ms": 20 }
+            ],
+            "loop_config": { "count": 1, "delay_between_ms": 0 },
+            "speed_multiplier": 1.0
+        }"#;
+
+        let loaded = Script::parse_lenient(json).unwrap();
+        assert_eq!(loaded.script.events.len(), 2);
+        assert_eq!(loaded.skipped.len(), 1);
+        assert_eq!(loaded.skipped[0].index, 1);
+    }
 }
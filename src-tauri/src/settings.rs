@@ -0,0 +1,223 @@
+//! Settings module - persisted application settings
+//! Stored as config.json in the app local data directory
+
+use crate::script::KeyboardKey;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Global settings state
+static SETTINGS_STATE: Lazy<Arc<SettingsState>> = Lazy::new(|| Arc::new(SettingsState::new()));
+
+/// Overlay appearance while recording or playing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayStyle {
+    /// Border color while recording
+    pub recording_color: String,
+    /// Border color while playing
+    pub playback_color: String,
+    /// Border width in pixels (0 hides the border)
+    pub border_width: u32,
+    /// Whether to show a small status label in the corner
+    pub show_label: bool,
+    /// Keep the overlay above other windows during playback
+    /// Turn off so screen recorders can sit above the border while demoing a script
+    #[serde(default = "default_playback_topmost")]
+    pub playback_topmost: bool,
+}
+
+fn default_playback_topmost() -> bool {
+    true
+}
+
+impl Default for OverlayStyle {
+    fn default() -> Self {
+        Self {
+            recording_color: "#f85149".to_string(),
+            playback_color: "#58a6ff".to_string(),
+            border_width: 4,
+            show_label: false,
+            playback_topmost: default_playback_topmost(),
+        }
+    }
+}
+
+/// Check that a color is a `#rgb` or `#rrggbb` hex string
+pub fn is_valid_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => {
+            (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+/// Application settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppSettings {
+    /// Overlay appearance
+    #[serde(default)]
+    pub overlay: OverlayStyle,
+    /// Ignore a key or button press landing within this window of the previous identical press,
+    /// released in between or not, before checking task triggers (0 disables it)
+    #[serde(default = "default_trigger_debounce_ms")]
+    pub trigger_debounce_ms: u64,
+    /// Keys still checked for task triggers while a script plays (e.g. a panic key)
+    /// Task stop keys always work during playback
+    #[serde(default)]
+    pub playback_passthrough_keys: Vec<KeyboardKey>,
+    /// Master switch: when off, playback, recording and task triggers are all refused
+    #[serde(default = "default_automation_enabled")]
+    pub automation_enabled: bool,
+}
+
+fn default_trigger_debounce_ms() -> u64 {
+    30
+}
+
+fn default_automation_enabled() -> bool {
+    true
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            overlay: OverlayStyle::default(),
+            trigger_debounce_ms: default_trigger_debounce_ms(),
+            playback_passthrough_keys: Vec::new(),
+            automation_enabled: default_automation_enabled(),
+        }
+    }
+}
+
+/// Settings state manager
+pub struct SettingsState {
+    /// Current settings
+    settings: RwLock<AppSettings>,
+    /// File the settings are persisted to (None until storage is initialized)
+    storage_path: RwLock<Option<PathBuf>>,
+}
+
+impl SettingsState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(AppSettings::default()),
+            storage_path: RwLock::new(None),
+        }
+    }
+
+    /// Load settings from `path` and persist future changes there
+    pub fn init_storage(&self, path: PathBuf) -> Result<(), String> {
+        if path.exists() {
+            let content =
+                fs::read_to_string(&path).map_err(|e| format!("File read error: {}", e))?;
+            let settings: AppSettings =
+                serde_json::from_str(&content).map_err(|e| format!("Parse error: {}", e))?;
+            *self.settings.write() = settings;
+        }
+        *self.storage_path.write() = Some(path);
+        Ok(())
+    }
+
+    /// Write the settings to the storage file, if one is configured
+    pub fn persist(&self) -> Result<(), String> {
+        let path = match self.storage_path.read().clone() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let json = serde_json::to_string_pretty(&*self.settings.read())
+            .map_err(|e| format!("Serialization error: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("File write error: {}", e))
+    }
+
+    pub fn get(&self) -> AppSettings {
+        self.settings.read().clone()
+    }
+
+    /// Apply a change to the settings and persist them
+    pub fn update(&self, change: impl FnOnce(&mut AppSettings)) -> Result<(), String> {
+        change(&mut self.settings.write());
+        self.persist()
+    }
+}
+
+impl Default for SettingsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the global settings state
+pub fn get_state() -> Arc<SettingsState> {
+    Arc::clone(&SETTINGS_STATE)
+}
+
+/// Load persisted settings and enable persistence
+pub fn init_storage(path: PathBuf) -> Result<(), String> {
+    get_state().init_storage(path)
+}
+
+/// Get a snapshot of the current settings
+pub fn get_settings() -> AppSettings {
+    get_state().get()
+}
+
+/// Replace the overlay style
+pub fn set_overlay_style(style: OverlayStyle) -> Result<(), String> {
+    for color in [&style.recording_color, &style.playback_color] {
+        if !is_valid_color(color) {
+            return Err(format!("Invalid color: {}", color));
+        }
+    }
+    get_state().update(|settings| settings.overlay = style)
+}
+
+/// Set the trigger debounce window (0 disables it)
+pub fn set_trigger_debounce_ms(ms: u64) -> Result<(), String> {
+    get_state().update(|settings| settings.trigger_debounce_ms = ms)
+}
+
+/// Replace the keys that task triggers still see during playback
+pub fn set_playback_passthrough_keys(keys: Vec<KeyboardKey>) -> Result<(), String> {
+    get_state().update(|settings| settings.playback_passthrough_keys = keys)
+}
+
+/// Turn the automation master switch on or off
+pub fn set_automation_enabled(enabled: bool) -> Result<(), String> {
+    get_state().update(|settings| settings.automation_enabled = enabled)
+}
+
+/// Fail if the automation master switch is off
+pub fn ensure_automation_enabled() -> Result<(), String> {
+    if get_settings().automation_enabled {
+        Ok(())
+    } else {
+        Err("Automation is disabled".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_validation() {
+        assert!(is_valid_color("#f85149"));
+        assert!(is_valid_color("#FFF"));
+        assert!(!is_valid_color("f85149"));
+        assert!(!is_valid_color("#12345"));
+        assert!(!is_valid_color("red'; alert(1); '"));
+    }
+
+    #[test]
+    fn test_overlay_style_defaults_to_topmost() {
+        let style: OverlayStyle = serde_json::from_str(
+            r##"{"recording_color":"#f00","playback_color":"#00f","border_width":4,"show_label":false}"##,
+        )
+        .unwrap();
+        assert!(style.playback_topmost);
+    }
+}
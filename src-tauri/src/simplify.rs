@@ -0,0 +1,176 @@
+//! Simplify module - shrinks recorded mouse paths
+//! Runs Ramer–Douglas–Peucker over consecutive MouseMove events
+
+use crate::script::ScriptEvent;
+
+/// A recorded move together with the delay that preceded it within its run
+struct PathPoint {
+    delay_ms: u64,
+    x: f64,
+    y: f64,
+}
+
+/// Distance from `p` to the line through `a` and `b`
+fn perpendicular_distance(p: &PathPoint, a: &PathPoint, b: &PathPoint) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return (p.x - a.x).hypot(p.y - a.y);
+    }
+    (dy * p.x - dx * p.y + b.x * a.y - b.y * a.x).abs() / length
+}
+
+/// Mark the points of `points[start..=end]` that must be kept
+fn douglas_peucker(
+    points: &[PathPoint],
+    start: usize,
+    end: usize,
+    epsilon: f64,
+    keep: &mut [bool],
+) {
+    if end <= start + 1 {
+        return;
+    }
+
+    let (mut farthest, mut max_distance) = (start, 0.0);
+    for i in start + 1..end {
+        let distance = perpendicular_distance(&points[i], &points[start], &points[end]);
+        if distance > max_distance {
+            farthest = i;
+            max_distance = distance;
+        }
+    }
+
+    if max_distance > epsilon {
+        keep[farthest] = true;
+        douglas_peucker(points, start, farthest, epsilon, keep);
+        douglas_peucker(points, farthest, end, epsilon, keep);
+    }
+}
+
+/// Emit the kept points of a run, folding removed points' delays into the next kept point
+fn flush_run(points: &mut Vec<PathPoint>, epsilon: f64, out: &mut Vec<ScriptEvent>) {
+    if points.is_empty() {
+        return;
+    }
+
+    let last = points.len() - 1;
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[last] = true;
+    douglas_peucker(points, 0, last, epsilon, &mut keep);
+
+    let mut pending_ms = 0;
+    for (point, kept) in points.drain(..).zip(keep) {
+        pending_ms += point.delay_ms;
+        if kept {
+            if pending_ms > 0 {
+                out.push(ScriptEvent::Delay {
+                    duration_ms: pending_ms,
+                });
+            }
+            out.push(ScriptEvent::MouseMove {
+                x: point.x,
+                y: point.y,
+            });
+            pending_ms = 0;
+        }
+    }
+}
+
+/// Drop near-collinear MouseMove events, keeping each run's endpoints
+/// A run is a sequence of moves separated only by delays; total timing is preserved
+pub fn simplify_path(events: Vec<ScriptEvent>, epsilon: f64) -> Vec<ScriptEvent> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut run: Vec<PathPoint> = Vec::new();
+    // Delays seen since the last move of the current run
+    let mut pending_ms = 0;
+
+    for event in events {
+        match event {
+            ScriptEvent::MouseMove { x, y } => {
+                run.push(PathPoint {
+                    delay_ms: pending_ms,
+                    x,
+                    y,
+                });
+                pending_ms = 0;
+            }
+            ScriptEvent::Delay { duration_ms } if !run.is_empty() => pending_ms += duration_ms,
+            other => {
+                flush_run(&mut run, epsilon, &mut out);
+                if pending_ms > 0 {
+                    out.push(ScriptEvent::Delay {
+                        duration_ms: pending_ms,
+                    });
+                    pending_ms = 0;
+                }
+                out.push(other);
+            }
+        }
+    }
+
+    flush_run(&mut run, epsilon, &mut out);
+    if pending_ms > 0 {
+        out.push(ScriptEvent::Delay {
+            duration_ms: pending_ms,
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::total_duration_ms;
+    use crate::script::MouseButton;
+
+    fn mv(x: f64, y: f64) -> ScriptEvent {
+        ScriptEvent::MouseMove { x, y }
+    }
+
+    fn delay(duration_ms: u64) -> ScriptEvent {
+        ScriptEvent::Delay { duration_ms }
+    }
+
+    #[test]
+    fn test_simplify_path_drops_collinear_moves() {
+        let click = ScriptEvent::MousePress {
+            button: MouseButton::Left,
+            x: 30.0,
+            y: 0.0,
+        };
+        let events = vec![
+            mv(0.0, 0.0),
+            delay(10),
+            mv(10.0, 0.1),
+            delay(10),
+            mv(20.0, 0.0),
+            delay(10),
+            mv(30.0, 0.0),
+            delay(5),
+            click.clone(),
+            mv(30.0, 30.0),
+        ];
+
+        let simplified = simplify_path(events.clone(), 1.0);
+        assert_eq!(
+            simplified,
+            vec![
+                mv(0.0, 0.0),
+                delay(30),
+                mv(30.0, 0.0),
+                delay(5),
+                click,
+                mv(30.0, 30.0),
+            ]
+        );
+        assert_eq!(total_duration_ms(&simplified), total_duration_ms(&events));
+    }
+
+    #[test]
+    fn test_simplify_path_keeps_corners() {
+        let events = vec![mv(0.0, 0.0), mv(10.0, 0.0), mv(10.0, 10.0)];
+        assert_eq!(simplify_path(events.clone(), 1.0), events);
+    }
+}
@@ -0,0 +1,128 @@
+//! Transform module - remaps recorded coordinates onto a moved or resized target
+//! Built from two calibration points clicked in the recording and on the current screen
+
+use crate::script::ScriptEvent;
+use serde::{Deserialize, Serialize};
+
+/// A screen position
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Per-axis scale followed by an offset: `x' = x * scale_x + offset_x`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+impl Transform {
+    /// Pure translation by (dx, dy)
+    pub fn offset(dx: f64, dy: f64) -> Self {
+        Self {
+            scale_x: 1.0,
+            scale_y: 1.0,
+            offset_x: dx,
+            offset_y: dy,
+        }
+    }
+
+    /// Transform mapping the recorded points `a`, `b` onto their current positions
+    /// The recorded points must differ on both axes, or the scale is undefined
+    pub fn calibrate(
+        record_a: Point,
+        record_b: Point,
+        current_a: Point,
+        current_b: Point,
+    ) -> Result<Self, String> {
+        let (record_dx, record_dy) = (record_b.x - record_a.x, record_b.y - record_a.y);
+        if record_dx.abs() < f64::EPSILON || record_dy.abs() < f64::EPSILON {
+            return Err("Calibration points must differ horizontally and vertically".to_string());
+        }
+
+        let scale_x = (current_b.x - current_a.x) / record_dx;
+        let scale_y = (current_b.y - current_a.y) / record_dy;
+        Ok(Self {
+            scale_x,
+            scale_y,
+            offset_x: current_a.x - record_a.x * scale_x,
+            offset_y: current_a.y - record_a.y * scale_y,
+        })
+    }
+
+    fn map(&self, x: &mut f64, y: &mut f64) {
+        *x = *x * self.scale_x + self.offset_x;
+        *y = *y * self.scale_y + self.offset_y;
+    }
+}
+
+/// Remap every coordinate in `events`; relative moves are scaled but not offset
+pub fn apply_transform(mut events: Vec<ScriptEvent>, transform: &Transform) -> Vec<ScriptEvent> {
+    for event in &mut events {
+        match event {
+            ScriptEvent::MousePress { x, y, .. }
+            | ScriptEvent::MouseRelease { x, y, .. }
+            | ScriptEvent::MouseMove { x, y }
+            | ScriptEvent::IfPixel { x, y, .. }
+            | ScriptEvent::WaitForPixelOptional { x, y, .. } => transform.map(x, y),
+            ScriptEvent::MouseScroll {
+                x: Some(x),
+                y: Some(y),
+                ..
+            } => transform.map(x, y),
+            ScriptEvent::MouseMoveRel { dx, dy } => {
+                *dx = (*dx as f64 * transform.scale_x).round() as i32;
+                *dy = (*dy as f64 * transform.scale_y).round() as i32;
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_calibrate_maps_both_points() {
+        let transform = Transform::calibrate(
+            point(100.0, 100.0),
+            point(300.0, 200.0),
+            point(250.0, 150.0),
+            point(650.0, 350.0),
+        )
+        .unwrap();
+
+        let events = apply_transform(
+            vec![
+                ScriptEvent::MouseMove { x: 100.0, y: 100.0 },
+                ScriptEvent::MouseMove { x: 300.0, y: 200.0 },
+                ScriptEvent::MouseMoveRel { dx: 10, dy: -5 },
+            ],
+            &transform,
+        );
+        assert_eq!(
+            events,
+            vec![
+                ScriptEvent::MouseMove { x: 250.0, y: 150.0 },
+                ScriptEvent::MouseMove { x: 650.0, y: 350.0 },
+                ScriptEvent::MouseMoveRel { dx: 20, dy: -10 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calibrate_rejects_aligned_points() {
+        let a = point(100.0, 100.0);
+        assert!(Transform::calibrate(a, point(100.0, 300.0), a, a).is_err());
+    }
+}
@@ -19,6 +19,7 @@ export const useScriptStore = defineStore('script', () => {
     const isRecording = ref(false);
     const isPlaying = ref(false);
     const isTaskListenerActive = ref(false);
+    const isAutomationEnabled = ref(true);
     const tasks = ref<Task[]>([]);
     const savedScripts = ref<SavedScript[]>([]);
     const selectedEventIndex = ref<number | null>(null);
@@ -198,6 +199,7 @@ export const useScriptStore = defineStore('script', () => {
             await invoke('start_recording');
             isRecording.value = true;
             currentScript.value.events = [];
+            currentScript.value.event_ids = [];
             statusMessage.value = '正在录制...';
         } catch (error) {
             statusMessage.value = `录制失败: ${error}`;
@@ -208,6 +210,9 @@ export const useScriptStore = defineStore('script', () => {
         try {
             const events = await invoke<ScriptEvent[]>('stop_recording');
             currentScript.value.events = events;
+            currentScript.value.event_ids = [];
+            currentScript.value = await invoke<Script>('assign_event_ids', { script: currentScript.value });
+            currentScript.value.scale_factor = await invoke<number>('get_recording_scale_factor');
             currentScript.value.modified_at = new Date().toISOString();
             isRecording.value = false;
             statusMessage.value = `录制完成 (${events.length} 个事件)`;
@@ -231,8 +236,18 @@ export const useScriptStore = defineStore('script', () => {
             return;
         }
 
+        // Infinite loops must be confirmed explicitly
+        let confirmInfinite = false;
+        if (currentScript.value.loop_config.count === 0) {
+            confirmInfinite = window.confirm('将无限循环播放，只能通过停止热键结束。确定继续吗？');
+            if (!confirmInfinite) return;
+        }
+
         try {
-            await invoke('play_script', { script: currentScript.value });
+            await invoke('play_script', {
+                script: currentScript.value,
+                confirmInfinite,
+            });
             isPlaying.value = true;
             statusMessage.value = '正在播放...';
         } catch (error) {
@@ -316,11 +331,10 @@ export const useScriptStore = defineStore('script', () => {
     }
 
     async function deleteEvent(index: number) {
-        const events = await invoke<ScriptEvent[]>('delete_event', {
-            events: currentScript.value.events,
+        currentScript.value = await invoke<Script>('delete_script_event', {
+            script: currentScript.value,
             index,
         });
-        currentScript.value.events = events;
         currentScript.value.modified_at = new Date().toISOString();
         if (selectedEventIndex.value === index) {
             selectedEventIndex.value = null;
@@ -379,6 +393,15 @@ export const useScriptStore = defineStore('script', () => {
         }
     }
 
+    async function toggleAutomation() {
+        try {
+            // The backend emits automation-enabled/disabled, which updates the state
+            await invoke('set_automation_enabled', { enabled: !isAutomationEnabled.value });
+        } catch (error) {
+            statusMessage.value = `切换自动化失败: ${error}`;
+        }
+    }
+
     async function createTaskBinding(
         name: string,
         triggerKey?: string,
@@ -440,6 +463,7 @@ export const useScriptStore = defineStore('script', () => {
             isPlaying.value = state.playing;
             // Note: backend field name changed in get_app_state 
             isTaskListenerActive.value = (state as any).task_listener_active;
+            isAutomationEnabled.value = state.automation_enabled;
         } catch (error) {
             console.error('Failed to sync state:', error);
         }
@@ -458,6 +482,20 @@ export const useScriptStore = defineStore('script', () => {
                 case 'playback-stopped':
                     statusMessage.value = '播放已停止';
                     break;
+                case 'playback-target-lost':
+                    statusMessage.value = '目标窗口已丢失，播放已中止';
+                    break;
+                case 'recording-stopped':
+                    statusMessage.value = '录制已停止';
+                    break;
+                case 'automation-disabled':
+                    isAutomationEnabled.value = false;
+                    statusMessage.value = '自动化已禁用';
+                    break;
+                case 'automation-enabled':
+                    isAutomationEnabled.value = true;
+                    statusMessage.value = '自动化已启用';
+                    break;
             }
         });
 
@@ -469,6 +507,8 @@ export const useScriptStore = defineStore('script', () => {
 
     async function handleFrontendEvent(e: KeyboardEvent) {
         if (!isRecording.value) return;
+        // Keys composing IME text; the committed text arrives via handleFrontendComposition
+        if (e.isComposing || e.keyCode === 229) return;
 
         let keyPayload;
         if (e.key.length === 1) {
@@ -505,12 +545,22 @@ export const useScriptStore = defineStore('script', () => {
         }
     }
 
+    async function handleFrontendComposition(e: CompositionEvent) {
+        if (!isRecording.value || !e.data) return;
+        try {
+            await invoke('record_frontend_composition', { text: e.data });
+        } catch (error) {
+            console.error('Failed to record composition:', error);
+        }
+    }
+
     return {
         // State
         currentScript,
         isRecording,
         isPlaying,
         isTaskListenerActive,
+        isAutomationEnabled,
         tasks,
         savedScripts,
         selectedEventIndex,
@@ -539,6 +589,7 @@ export const useScriptStore = defineStore('script', () => {
         loadTasks,
         listSavedScripts,
         toggleTaskListener,
+        toggleAutomation,
         createTaskBinding,
         removeTask,
         toggleTaskEnabled,
@@ -546,6 +597,7 @@ export const useScriptStore = defineStore('script', () => {
         syncState,
         init,
         handleFrontendEvent,
+        handleFrontendComposition,
         createNewDraftScript,
         notifications,
         showNotification,
@@ -4,7 +4,8 @@
  */
 
 // Mouse button types
-export type MouseButton = 'left' | 'right' | 'middle' | 'back' | 'forward';
+type NamedMouseButton = 'left' | 'right' | 'middle' | 'back' | 'forward' | 'unknown';
+export type MouseButton = NamedMouseButton | { extra: number };
 
 // Keyboard key representation
 export type KeyboardKey =
@@ -20,10 +21,12 @@ export type ScriptEvent =
     | {
         event_type: 'KeyPress';
         key: KeyboardKey;
+        key_code?: number;
     }
     | {
         event_type: 'KeyRelease';
         key: KeyboardKey;
+        key_code?: number;
     }
     | {
         event_type: 'MousePress';
@@ -42,10 +45,79 @@ export type ScriptEvent =
         x: number;
         y: number;
     }
+    | {
+        event_type: 'MouseMoveRel';
+        dx: number;
+        dy: number;
+    }
     | {
         event_type: 'MouseScroll';
         delta_x: number;
         delta_y: number;
+        x?: number;
+        y?: number;
+    }
+    | {
+        event_type: 'IfPixel';
+        x: number;
+        y: number;
+        rgb: [number, number, number];
+        tolerance: number;
+        then_jump: number;
+        else_jump: number;
+    }
+    | {
+        event_type: 'WaitForPixelOptional';
+        x: number;
+        y: number;
+        rgb: [number, number, number];
+        tolerance: number;
+        max_wait_ms: number;
+    }
+    | {
+        event_type: 'Jump';
+        target: number;
+    }
+    | {
+        event_type: 'KeyRepeat';
+        key: KeyboardKey;
+        count: number;
+        interval_ms: number;
+    }
+    | {
+        event_type: 'RunTask';
+        id: string;
+    }
+    | {
+        event_type: 'Disabled';
+        event: ScriptEvent;
+    }
+    | {
+        event_type: 'Label';
+        name: string;
+    }
+    | {
+        event_type: 'CaptureClipboard';
+        var: string;
+    }
+    | {
+        event_type: 'TypeVar';
+        var: string;
+    }
+    | {
+        event_type: 'TypeText';
+        text: string;
+    }
+    | {
+        event_type: 'WindowAnchor';
+        title: string;
+        x: number;
+        y: number;
+    }
+    | {
+        event_type: 'Chord';
+        modifiers: KeyboardKey[];
+        key: KeyboardKey;
     };
 
 // A task definition - trigger + action
@@ -56,9 +128,17 @@ export interface Task {
     trigger_key?: KeyboardKey;
     stop_key?: KeyboardKey;
     script_path: string;
+    events?: ScriptEvent[] | null;
     enabled: boolean;
     loop_config: LoopConfig;
     speed_multiplier: number;
+    requires_lock_state?: LockState | null;
+}
+
+// Required NumLock/CapsLock state for a task trigger (null/absent = either)
+export interface LockState {
+    num_lock?: boolean | null;
+    caps_lock?: boolean | null;
 }
 
 // Saved script info
@@ -84,18 +164,51 @@ export interface Script {
     events: ScriptEvent[];
     loop_config: LoopConfig;
     speed_multiplier: number;
+    ramp_events?: number;
+    global_event_padding_ms?: number;
+    click_settle_ms?: number;
+    physical_keys?: boolean;
+    symbols_as_text?: boolean;
+    honor_disabled_delays?: boolean;
+    restore_cursor?: boolean;
+    absolute_timing?: boolean;
+    clamp_to_screen?: boolean;
+    watch_target_window?: boolean;
+    scale_factor?: number | null;
+    checksum?: string;
+    /** Stable id of each event, parallel to `events` */
+    event_ids?: number[];
+}
+
+// Overlay appearance
+export interface OverlayStyle {
+    recording_color: string;
+    playback_color: string;
+    border_width: number;
+    show_label: boolean;
+    playback_topmost: boolean;
+}
+
+// Persisted application settings
+export interface AppSettings {
+    overlay: OverlayStyle;
+    trigger_debounce_ms: number;
+    playback_passthrough_keys: KeyboardKey[];
+    automation_enabled: boolean;
 }
 
 // App state
 export interface AppState {
     recording: boolean;
     playing: boolean;
+    stepping: boolean;
     task_listener_active: boolean;
+    automation_enabled: boolean;
 }
 
 // Hotkey event payload
 export interface HotkeyEvent {
-    action: 'recording-started' | 'recording-stopped' | 'playback-requested' | 'playback-stopped' | 'emergency-stop';
+    action: 'recording-started' | 'recording-stopped' | 'playback-requested' | 'playback-stopped' | 'playback-target-lost' | 'emergency-stop' | 'automation-enabled' | 'automation-disabled';
     recording: boolean;
     playing: boolean;
 }
@@ -117,12 +230,16 @@ export function getKeyDisplay(key: KeyboardKey): string {
 
 export function getEventDescription(event: ScriptEvent): string {
     const translateButton = (btn: MouseButton) => {
-        const map: Record<MouseButton, string> = {
+        if (typeof btn === 'object') {
+            return `侧键 ${btn.extra}`;
+        }
+        const map: Record<NamedMouseButton, string> = {
             left: '左键',
             right: '右键',
             middle: '中键',
             back: '后退键',
-            forward: '前进键'
+            forward: '前进键',
+            unknown: '未知键'
         };
         return map[btn] || btn;
     };
@@ -140,6 +257,8 @@ export function getEventDescription(event: ScriptEvent): string {
             return `鼠标弹起 (${translateButton(event.button)})`;
         case 'MouseMove':
             return `鼠标移动 (${Math.round(event.x)}, ${Math.round(event.y)})`;
+        case 'MouseMoveRel':
+            return `鼠标相对移动 (${event.dx}, ${event.dy})`;
         case 'MouseScroll':
             return `鼠标滚动 (${event.delta_x}, ${event.delta_y})`;
         default: